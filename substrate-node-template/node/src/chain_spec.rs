@@ -1,11 +1,12 @@
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY,
+	social_graph::GraphParameters, AccountId, AuraConfig, BalancesConfig, GenesisConfig,
+	GrandpaConfig, Percent, Signature, SocialGraphConfig, SudoConfig, SystemConfig, WASM_BINARY,
 };
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{sr25519, Pair, Public};
 use sp_finality_grandpa::AuthorityId as GrandpaId;
+use sp_keyring::Sr25519Keyring;
 use sp_runtime::traits::{IdentifyAccount, Verify};
 
 // The URL for the telemetry server.
@@ -124,6 +125,170 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 	))
 }
 
+/// Number of generated accounts in the honest cluster, besides the well-known ones.
+const DEMO_HONEST: usize = 15;
+/// Number of generated accounts in the sybil cluster, besides Ferdie.
+const DEMO_SYBILS: usize = 9;
+/// How many of the following honest accounts each honest account attests.
+const DEMO_HONEST_DEGREE: usize = 7;
+
+/// A development chain whose social graph starts with an honest cluster, a
+/// sybil cluster and a few bridges between them, and with Alice's challenge
+/// of Ferdie open.
+pub fn social_demo_config() -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
+
+	Ok(ChainSpec::from_genesis(
+		// Name
+		"Social Demo",
+		// ID
+		"social_demo",
+		ChainType::Development,
+		move || {
+			let account = |seed: &str| get_account_id_from_seed::<sr25519::Public>(seed);
+			let honest: Vec<_> = ["Alice", "Bob", "Charlie", "Dave", "Eve"]
+				.iter()
+				.map(|seed| account(seed))
+				.chain((0..DEMO_HONEST).map(|i| account(&format!("Honest//{}", i))))
+				.collect();
+			let sybils: Vec<_> = std::iter::once(account("Ferdie"))
+				.chain((0..DEMO_SYBILS).map(|i| account(&format!("Sybil//{}", i))))
+				.collect();
+
+			let mut attestations = Vec::new();
+			// Honest accounts each vouch for the next few around a ring, which
+			// keeps them above the network averages so they can vote.
+			for (i, attester) in honest.iter().enumerate() {
+				for step in 1..=DEMO_HONEST_DEGREE {
+					let target = &honest[(i + step) % honest.len()];
+					attestations.push((attester.clone(), target.clone(), 10));
+				}
+			}
+			// Sybils all vouch for each other with full confidence.
+			for attester in &sybils {
+				for target in sybils.iter().filter(|target| *target != attester) {
+					attestations.push((attester.clone(), target.clone(), 10));
+				}
+			}
+			// A few honest accounts were fooled into vouching for sybils.
+			for (attester, target, confidence) in [(3, 0, 4), (4, 1, 5), (12, 2, 3)] {
+				attestations.push((honest[attester].clone(), sybils[target].clone(), confidence));
+			}
+
+			let endowed_accounts = honest
+				.iter()
+				.chain(&sybils)
+				.cloned()
+				.chain(["Alice//stash", "Bob//stash"].iter().map(|seed| account(seed)))
+				.collect();
+			let mut genesis = testnet_genesis(
+				wasm_binary,
+				// Initial PoA authorities
+				vec![authority_keys_from_seed("Alice")],
+				// Sudo account
+				account("Alice"),
+				// Pre-funded accounts
+				endowed_accounts,
+				true,
+			);
+			let social_graph = &mut genesis.social_graph;
+			// Leave time to vote on the open challenge.
+			social_graph.parameters.challenge_duration = 100;
+			social_graph.attestations = attestations;
+			social_graph.challenges = vec![(honest[0].clone(), sybils[0].clone())];
+			genesis
+		},
+		// Bootnodes
+		vec![],
+		// Telemetry
+		None,
+		// Protocol ID
+		None,
+		None,
+		// Properties
+		None,
+		// Extensions
+		None,
+	))
+}
+
+/// Dev accounts that all vouch for each other on the benchmark chain, and
+/// sign the extrinsics of `benchmark overhead`.
+pub const BENCHMARK_SIGNERS: [Sr25519Keyring; 6] = [
+	Sr25519Keyring::Alice,
+	Sr25519Keyring::Bob,
+	Sr25519Keyring::Charlie,
+	Sr25519Keyring::Dave,
+	Sr25519Keyring::Eve,
+	Sr25519Keyring::Ferdie,
+];
+
+/// Number of sybils on the benchmark chain, as many as there can be challenges.
+const BENCHMARK_SYBILS: u32 = 1_000;
+
+/// The benchmark chain's sybils, each vouching for the next around a ring.
+/// Alice's challenge of the first one is open from genesis.
+pub fn benchmark_sybils() -> Vec<AccountId> {
+	(0..BENCHMARK_SYBILS)
+		.map(|i| get_account_id_from_seed::<sr25519::Public>(&format!("Sybil//{}", i)))
+		.collect()
+}
+
+/// A development chain for `benchmark overhead`, with a social graph in
+/// which every benchmark signer is valid and a challenge is open to vote on.
+pub fn benchmark_config() -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
+
+	Ok(ChainSpec::from_genesis(
+		// Name
+		"Social Graph Benchmark",
+		// ID
+		"benchmark",
+		ChainType::Development,
+		move || {
+			let signers: Vec<_> =
+				BENCHMARK_SIGNERS.iter().map(|signer| signer.to_account_id()).collect();
+			let sybils = benchmark_sybils();
+
+			let mut attestations = Vec::new();
+			for attester in &signers {
+				for target in signers.iter().filter(|target| *target != attester) {
+					attestations.push((attester.clone(), target.clone(), 10));
+				}
+			}
+			for (i, attester) in sybils.iter().enumerate() {
+				attestations.push((attester.clone(), sybils[(i + 1) % sybils.len()].clone(), 10));
+			}
+
+			let mut genesis = testnet_genesis(
+				wasm_binary,
+				// Initial PoA authorities
+				vec![authority_keys_from_seed("Alice")],
+				// Sudo account
+				signers[0].clone(),
+				// Pre-funded accounts
+				signers.clone(),
+				true,
+			);
+			let social_graph = &mut genesis.social_graph;
+			social_graph.attestations = attestations;
+			social_graph.challenges = vec![(signers[0].clone(), sybils[0].clone())];
+			genesis
+		},
+		// Bootnodes
+		vec![],
+		// Telemetry
+		None,
+		// Protocol ID
+		None,
+		None,
+		// Properties
+		None,
+		// Extensions
+		None,
+	))
+}
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
@@ -152,5 +317,24 @@ fn testnet_genesis(
 			key: Some(root_key),
 		},
 		transaction_payment: Default::default(),
+		council: Default::default(),
+		social_graph: SocialGraphConfig {
+			parameters: GraphParameters {
+				challenge_duration: 8,
+				max_confidence: 10,
+				min_mutual_attestations: 0,
+				max_mutual_share: Percent::from_percent(100),
+				registration_deposit: 1_000_000,
+				challenge_bond: 10_000_000,
+				veto_period: 10,
+				recovery_threshold: Percent::from_percent(50),
+				recovery_delay: 100,
+				cancel_refund: Percent::from_percent(50),
+				min_turnout: 5,
+			},
+			attestations: vec![],
+			banned: vec![],
+			challenges: vec![],
+		},
 	}
 }
@@ -1,4 +1,6 @@
+use crate::service::Sealing;
 use sc_cli::RunCmd;
+use std::path::PathBuf;
 
 #[derive(Debug, clap::Parser)]
 pub struct Cli {
@@ -7,6 +9,17 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	/// Index social-graph events of finalized blocks into an SQLite database
+	/// at this path and serve history RPCs from it.
+	#[clap(long, value_name = "PATH")]
+	pub indexer_path: Option<PathBuf>,
+
+	/// Author blocks on demand instead of with Aura and GRANDPA, for development.
+	/// `manual` seals a block when `engine_createBlock` or `engine_createBlocks`
+	/// is called, and `instant` also seals one for each new transaction.
+	#[clap(long, arg_enum, value_name = "MODE")]
+	pub sealing: Option<Sealing>,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -27,6 +40,12 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Export the social graph's attestations at a given block.
+	ExportGraph(crate::export_graph::ExportGraphCmd),
+
+	/// Build a chain specification seeded with a trust graph.
+	ImportGraph(crate::import_graph::ImportGraphCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
@@ -19,41 +19,81 @@
 //! Contains code to setup the command invocations in [`super::command`] which would
 //! otherwise bloat that module.
 
-use crate::service::FullClient;
+use crate::{
+	chain_spec::{benchmark_sybils, BENCHMARK_SIGNERS},
+	service::FullClient,
+};
 
 use node_template_runtime as runtime;
-use runtime::SystemCall;
+use runtime::{social_graph::Relationship, AccountId, SocialGraphCall};
 use sc_cli::Result;
 use sc_client_api::BlockBackend;
 use sp_core::{Encode, Pair};
 use sp_inherents::{InherentData, InherentDataProvider};
-use sp_keyring::Sr25519Keyring;
 use sp_runtime::{OpaqueExtrinsic, SaturatedConversion};
 
 use std::{sync::Arc, time::Duration};
 
 /// Generates extrinsics for the `benchmark overhead` command.
 ///
+/// Rather than remarks, these are social-graph attestations, challenges and
+/// votes signed in turn by the [`BENCHMARK_SIGNERS`], so the benchmark runs
+/// on the `benchmark` chain, whose genesis makes them succeed.
+///
 /// Note: Should only be used for benchmarking.
 pub struct BenchmarkExtrinsicBuilder {
 	client: Arc<FullClient>,
+	sybils: Vec<AccountId>,
 }
 
 impl BenchmarkExtrinsicBuilder {
 	/// Creates a new [`Self`] from the given client.
 	pub fn new(client: Arc<FullClient>) -> Self {
-		Self { client }
+		Self { client, sybils: benchmark_sybils() }
+	}
+}
+
+/// The call of the `round`th extrinsic of the signer at `index`. Rounds
+/// alternate between attestations, challenges and votes:
+/// - attestations renew the signers' genesis attestations of each other,
+///   so they stay valid,
+/// - challenges each target a different sybil, until all are challenged,
+/// - votes are cast on the sybil challenged at genesis.
+fn social_graph_call(
+	sybils: &[AccountId],
+	index: usize,
+	round: u32,
+) -> SocialGraphCall<runtime::Runtime> {
+	let signers = BENCHMARK_SIGNERS.len();
+	let step = (round / 3) as usize;
+	match round % 3 {
+		0 => {
+			let target = BENCHMARK_SIGNERS[(index + 1 + step % (signers - 1)) % signers];
+			SocialGraphCall::attest {
+				target: target.to_account_id().into(),
+				confidence: 10,
+				relationship: Relationship::Unspecified,
+				known_for: 0,
+			}
+		},
+		1 => {
+			let suspect = &sybils[1 + (step * signers + index) % (sybils.len() - 1)];
+			SocialGraphCall::challenge { suspect: suspect.clone().into() }
+		},
+		_ => SocialGraphCall::vote { suspect: sybils[0].clone(), value: 10 },
 	}
 }
 
 impl frame_benchmarking_cli::ExtrinsicBuilder for BenchmarkExtrinsicBuilder {
 	fn remark(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
-		let acc = Sr25519Keyring::Bob.pair();
+		// Extrinsics go round the signers, so each signer's nonce is the round.
+		let index = nonce as usize % BENCHMARK_SIGNERS.len();
+		let round = nonce / BENCHMARK_SIGNERS.len() as u32;
 		let extrinsic: OpaqueExtrinsic = create_benchmark_extrinsic(
 			self.client.as_ref(),
-			acc,
-			SystemCall::remark { remark: vec![] }.into(),
-			nonce,
+			BENCHMARK_SIGNERS[index].pair(),
+			social_graph_call(&self.sybils, index, round).into(),
+			round,
 		)
 		.into();
 
@@ -129,3 +169,53 @@ pub fn inherent_benchmark_data() -> Result<InherentData> {
 		.map_err(|e| format!("creating inherent data: {:?}", e))?;
 	Ok(inherent_data)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::weights::{DispatchClass, GetDispatchInfo};
+
+	/// A block of benchmark extrinsics must be limited by the block weight
+	/// rather than a single expensive call, must hold plenty of each of the
+	/// attestations, challenges and votes, and must not run out of sybils.
+	#[test]
+	fn benchmark_block_fits_enough_extrinsics() {
+		let sybils = benchmark_sybils();
+		let limits = runtime::BlockWeights::get();
+		let normal = limits.get(DispatchClass::Normal);
+		let max_total = normal.max_total.unwrap_or(limits.max_block);
+
+		let (mut total, mut extrinsics) = (0, 0);
+		let (mut attestations, mut challenges, mut votes) = (0, 0, 0);
+		loop {
+			let index = extrinsics % BENCHMARK_SIGNERS.len();
+			let round = (extrinsics / BENCHMARK_SIGNERS.len()) as u32;
+			let call = social_graph_call(&sybils, index, round);
+			total += normal.base_extrinsic + call.get_dispatch_info().weight;
+			if total > max_total {
+				break
+			}
+			match call {
+				SocialGraphCall::attest { .. } => attestations += 1,
+				SocialGraphCall::challenge { .. } => challenges += 1,
+				SocialGraphCall::vote { .. } => votes += 1,
+				call => panic!("unexpected benchmark call {:?}", call),
+			}
+			extrinsics += 1;
+		}
+
+		assert!(extrinsics >= 1_000, "only {} extrinsics fit in a block", extrinsics);
+		for (name, count) in
+			[("attestations", attestations), ("challenges", challenges), ("votes", votes)]
+		{
+			assert!(count >= 300, "only {} {} fit in a block", count, name);
+		}
+		// The first sybil is challenged at genesis.
+		assert!(
+			challenges < sybils.len(),
+			"{} challenges for {} unchallenged sybils",
+			challenges,
+			sybils.len() - 1
+		);
+	}
+}
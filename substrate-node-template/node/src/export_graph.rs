@@ -0,0 +1,304 @@
+//! The `export-graph` subcommand: dumps the attestation graph at a block from
+//! the local database for offline analysis.
+
+use crate::service::FullClient;
+use codec::Decode;
+use node_template_runtime::{opaque::Block, social_graph::Attestation, AccountId, BlockNumber};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::StorageProvider;
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, hashing::twox_128, storage::StorageKey};
+use sp_runtime::generic::BlockId;
+use std::{
+	collections::BTreeMap,
+	fs::File,
+	io::{self, Write},
+	path::PathBuf,
+	sync::Arc,
+};
+
+/// Length of a `Blake2_128Concat` hash before the key it hashes.
+const BLAKE2_128_LEN: usize = 16;
+
+/// Output formats of `export-graph`.
+#[derive(Debug, Clone, Copy, clap::ArgEnum)]
+pub enum GraphFormat {
+	/// GraphML, for Gephi and NetworkX
+	Graphml,
+	/// Graphviz DOT
+	Dot,
+	/// CSV list of edges
+	Csv,
+	/// JSON with the accounts and the edges
+	Json,
+}
+
+/// Export the social graph's attestations at a block.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportGraphCmd {
+	/// Block to read the graph at. Defaults to the best block. Older blocks
+	/// need their state to be kept, for example with `--state-pruning archive`.
+	#[clap(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Output format.
+	#[clap(long, arg_enum, default_value = "graphml")]
+	pub format: GraphFormat,
+
+	/// File to write the graph to. Defaults to stdout.
+	#[clap(long, short = 'o', value_name = "PATH")]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// An account of the graph. Attesters that left or were retired still have
+/// their attestations in the graph, so they appear unregistered.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Node {
+	/// Whether the account has AccountData
+	registered: bool,
+	/// Number of attestations received, if registered
+	attest_count: Option<u32>,
+	/// Sum of the weighted confidence received, if registered
+	confidence: Option<u32>,
+	/// Block the account registered, if registered
+	born: Option<BlockNumber>,
+	/// Block the account was banned, if banned
+	banned: Option<BlockNumber>,
+}
+
+/// An attestation of the graph.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Edge {
+	attester: String,
+	target: String,
+	confidence: u8,
+	relationship: String,
+	known_for: u16,
+	block: BlockNumber,
+}
+
+/// The graph as written in JSON.
+#[derive(Debug, Serialize)]
+struct Graph {
+	block: String,
+	accounts: BTreeMap<String, Node>,
+	attestations: Vec<Edge>,
+}
+
+impl ExportGraphCmd {
+	/// Reads the graph from the client's database and writes it out.
+	pub fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+		let hash = match &self.at {
+			Some(at) => {
+				let id = at.parse::<Block>().map_err(sc_cli::Error::Input)?;
+				client
+					.block_hash_from_id(&id)?
+					.ok_or_else(|| sc_cli::Error::Input(format!("Unknown block {:?}", at)))?
+			},
+			None => client.info().best_hash,
+		};
+		let at = BlockId::Hash(hash);
+
+		let mut accounts: BTreeMap<String, Node> = BTreeMap::new();
+		for (key, value) in read_map(&client, &at, b"AccountData")? {
+			let (attest_count, confidence, born) = decode::<(u32, u32, BlockNumber)>(&value)?;
+			let node = accounts.entry(key[0].to_ss58check()).or_default();
+			node.registered = true;
+			node.attest_count = Some(attest_count);
+			node.confidence = Some(confidence);
+			node.born = Some(born);
+		}
+		for (key, value) in read_map(&client, &at, b"BannedAccounts")? {
+			accounts.entry(key[0].to_ss58check()).or_default().banned =
+				Some(decode::<BlockNumber>(&value)?);
+		}
+
+		// Attestations are keyed by target, then attester. Every endpoint gets
+		// a node, so no edge refers to a missing one.
+		let mut attestations = Vec::new();
+		for (key, value) in read_map(&client, &at, b"Attestations")? {
+			let attestation = decode::<Attestation<BlockNumber>>(&value)?;
+			let (attester, target) = (key[1].to_ss58check(), key[0].to_ss58check());
+			accounts.entry(attester.clone()).or_default();
+			accounts.entry(target.clone()).or_default();
+			attestations.push(Edge {
+				attester,
+				target,
+				confidence: attestation.confidence,
+				relationship: format!("{:?}", attestation.relationship),
+				known_for: attestation.known_for,
+				block: attestation.block,
+			});
+		}
+
+		let graph = Graph { block: format!("{:?}", hash), accounts, attestations };
+		let mut out: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(io::stdout()),
+		};
+		match self.format {
+			GraphFormat::Graphml => write_graphml(&mut out, &graph)?,
+			GraphFormat::Dot => write_dot(&mut out, &graph)?,
+			GraphFormat::Csv => write_csv(&mut out, &graph)?,
+			GraphFormat::Json => serde_json::to_writer_pretty(&mut out, &graph)
+				.map_err(|e| sc_cli::Error::Application(Box::new(e)))?,
+		}
+		out.flush()?;
+		Ok(())
+	}
+}
+
+/// Reads every entry of a social-graph map whose keys are all accounts
+/// hashed with `Blake2_128Concat`, returning the decoded keys and the raw value.
+fn read_map(
+	client: &FullClient,
+	at: &BlockId<Block>,
+	item: &[u8],
+) -> sc_cli::Result<Vec<(Vec<AccountId>, Vec<u8>)>> {
+	let prefix = [twox_128(b"SocialGraph"), twox_128(item)].concat();
+	let mut entries = Vec::new();
+	for (key, value) in client.storage_pairs(at, &StorageKey(prefix.clone()))? {
+		let mut input = &key.0[prefix.len()..];
+		let mut accounts = Vec::new();
+		while !input.is_empty() {
+			input = input.get(BLAKE2_128_LEN..).unwrap_or_default();
+			accounts.push(AccountId::decode(&mut input).map_err(|e| {
+				sc_cli::Error::Input(format!("Undecodable storage key: {}", e))
+			})?);
+		}
+		entries.push((accounts, value.0));
+	}
+	Ok(entries)
+}
+
+fn decode<T: Decode>(mut input: &[u8]) -> sc_cli::Result<T> {
+	T::decode(&mut input).map_err(|e| sc_cli::Error::Input(format!("Undecodable storage: {}", e)))
+}
+
+fn write_graphml(out: &mut impl Write, graph: &Graph) -> io::Result<()> {
+	writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+	writeln!(out, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+	for (id, owner, kind) in [
+		("registered", "node", "boolean"),
+		("attest_count", "node", "int"),
+		("confidence", "node", "int"),
+		("born", "node", "int"),
+		("banned", "node", "int"),
+		("confidence", "edge", "int"),
+		("relationship", "edge", "string"),
+		("known_for", "edge", "int"),
+		("block", "edge", "int"),
+	] {
+		writeln!(
+			out,
+			r#"  <key id="{owner}_{id}" for="{owner}" attr.name="{id}" attr.type="{kind}"/>"#
+		)?;
+	}
+	writeln!(out, r#"  <graph id="{}" edgedefault="directed">"#, graph.block)?;
+	for (account, node) in &graph.accounts {
+		writeln!(out, r#"    <node id="{}">"#, account)?;
+		writeln!(out, r#"      <data key="node_registered">{}</data>"#, node.registered)?;
+		for (id, value) in [
+			("attest_count", node.attest_count),
+			("confidence", node.confidence),
+			("born", node.born),
+			("banned", node.banned),
+		] {
+			if let Some(value) = value {
+				writeln!(out, r#"      <data key="node_{}">{}</data>"#, id, value)?;
+			}
+		}
+		writeln!(out, "    </node>")?;
+	}
+	for edge in &graph.attestations {
+		writeln!(out, r#"    <edge source="{}" target="{}">"#, edge.attester, edge.target)?;
+		writeln!(out, r#"      <data key="edge_confidence">{}</data>"#, edge.confidence)?;
+		writeln!(out, r#"      <data key="edge_relationship">{}</data>"#, edge.relationship)?;
+		writeln!(out, r#"      <data key="edge_known_for">{}</data>"#, edge.known_for)?;
+		writeln!(out, r#"      <data key="edge_block">{}</data>"#, edge.block)?;
+		writeln!(out, "    </edge>")?;
+	}
+	writeln!(out, "  </graph>")?;
+	writeln!(out, "</graphml>")
+}
+
+fn write_dot(out: &mut impl Write, graph: &Graph) -> io::Result<()> {
+	writeln!(out, "digraph social_graph {{")?;
+	for (account, node) in &graph.accounts {
+		let mut attributes = Vec::new();
+		if !node.registered {
+			attributes.push("registered=false".into());
+			attributes.push("style=dashed".into());
+		}
+		if let Some(count) = node.attest_count {
+			attributes.push(format!("attest_count={}", count));
+		}
+		if let Some(confidence) = node.confidence {
+			attributes.push(format!("confidence={}", confidence));
+		}
+		if let Some(banned) = node.banned {
+			attributes.push(format!("banned={}", banned));
+			attributes.push("color=red".into());
+		}
+		writeln!(out, "  \"{}\" [{}];", account, attributes.join(", "))?;
+	}
+	for edge in &graph.attestations {
+		writeln!(
+			out,
+			"  \"{}\" -> \"{}\" [confidence={}, relationship={}, known_for={}, block={}];",
+			edge.attester,
+			edge.target,
+			edge.confidence,
+			edge.relationship,
+			edge.known_for,
+			edge.block
+		)?;
+	}
+	writeln!(out, "}}")
+}
+
+fn write_csv(out: &mut impl Write, graph: &Graph) -> io::Result<()> {
+	writeln!(out, "attester,target,confidence,relationship,known_for,block")?;
+	for edge in &graph.attestations {
+		writeln!(
+			out,
+			"{},{},{},{},{},{}",
+			edge.attester,
+			edge.target,
+			edge.confidence,
+			edge.relationship,
+			edge.known_for,
+			edge.block
+		)?;
+	}
+	Ok(())
+}
+
+impl CliConfiguration for ExportGraphCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -0,0 +1,314 @@
+//! The `import-graph` subcommand: seeds a chain spec's social-graph genesis
+//! with a trust graph collected off-chain.
+
+use crate::chain_spec::ChainSpec;
+use node_template_runtime::AccountId;
+use sc_cli::{CliConfiguration, SharedParams};
+use serde::Deserialize;
+use sp_core::crypto::Ss58Codec;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs,
+	io::Write,
+	path::{Path, PathBuf},
+};
+
+/// Confidence limit used when the chain spec doesn't set `max_confidence`.
+const DEFAULT_MAX_CONFIDENCE: u64 = 10;
+
+/// Write a chain spec whose social graph starts with the given attestations and bans.
+/// The base spec's genesis challenges refer to its own accounts, so they are dropped.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ImportGraphCmd {
+	/// Trust graph to import. A `.json` file holds `attestations` as objects
+	/// with `attester`, `target` and `confidence`, and optionally `bans`.
+	/// Any other file is read as CSV lines of `attester,target,confidence`.
+	/// Accounts are SS58 encoded.
+	#[clap(value_name = "FILE")]
+	pub input: PathBuf,
+
+	/// File of accounts to ban at genesis, one SS58 address per line.
+	#[clap(long, value_name = "FILE")]
+	pub bans: Option<PathBuf>,
+
+	/// Write the chain spec in raw storage form.
+	#[clap(long)]
+	pub raw: bool,
+
+	/// File to write the chain spec to. Defaults to stdout.
+	#[clap(long, short = 'o', value_name = "PATH")]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// A trust graph in JSON.
+#[derive(Debug, Deserialize)]
+struct JsonGraph {
+	attestations: Vec<JsonAttestation>,
+	#[serde(default)]
+	bans: Vec<String>,
+}
+
+/// An attestation in JSON.
+#[derive(Debug, Deserialize)]
+struct JsonAttestation {
+	attester: String,
+	target: String,
+	confidence: u64,
+}
+
+/// A validated trust graph.
+#[derive(Debug, Default)]
+struct TrustGraph {
+	attestations: Vec<(AccountId, AccountId, u8)>,
+	bans: BTreeSet<AccountId>,
+}
+
+impl ImportGraphCmd {
+	/// Reads the trust graph, writes it into the chain spec's genesis and
+	/// prints summary statistics to stderr.
+	pub fn run(&self, spec: Box<dyn sc_service::ChainSpec>) -> sc_cli::Result<()> {
+		let mut json: serde_json::Value = serde_json::from_str(&spec.as_json(false)?)
+			.map_err(|e| sc_cli::Error::Application(Box::new(e)))?;
+		let social_graph = json
+			.pointer_mut("/genesis/runtime/socialGraph")
+			.and_then(|value| value.as_object_mut())
+			.ok_or_else(|| {
+				sc_cli::Error::Input("The chain spec has no social-graph genesis to seed".into())
+			})?;
+		let max_confidence = max_confidence(social_graph);
+
+		let graph = self.read_graph(max_confidence)?;
+		seed(social_graph, &graph);
+
+		let mut spec = serde_json::to_string_pretty(&json)
+			.map_err(|e| sc_cli::Error::Application(Box::new(e)))?;
+		if self.raw {
+			spec = ChainSpec::from_json_bytes(spec.into_bytes())
+				.map_err(sc_cli::Error::Input)?
+				.as_json(true)?;
+		}
+		match &self.output {
+			Some(path) => fs::write(path, spec)?,
+			None => std::io::stdout().write_all(spec.as_bytes())?,
+		}
+
+		print_summary(&graph);
+		Ok(())
+	}
+
+	/// Reads and validates the trust graph and the bans.
+	fn read_graph(&self, max_confidence: u64) -> sc_cli::Result<TrustGraph> {
+		let contents = fs::read_to_string(&self.input)?;
+		let is_json = self.input.extension().map_or(false, |extension| extension == "json");
+		let (edges, bans) = if is_json {
+			let graph: JsonGraph = serde_json::from_str(&contents)
+				.map_err(|e| sc_cli::Error::Input(format!("Invalid graph JSON: {}", e)))?;
+			let edges = graph
+				.attestations
+				.into_iter()
+				.enumerate()
+				.map(|(i, a)| (format!("attestation {}", i), a.attester, a.target, a.confidence))
+				.collect();
+			(edges, graph.bans)
+		} else {
+			(read_csv(&self.input, &contents)?, Vec::new())
+		};
+
+		let mut graph = TrustGraph::default();
+		let mut seen = BTreeSet::new();
+		for (at, attester, target, confidence) in edges {
+			let attester = parse_account(&attester, &at)?;
+			let target = parse_account(&target, &at)?;
+			if attester == target {
+				return Err(sc_cli::Error::Input(format!("{}: self-attestation", at)))
+			}
+			if confidence > max_confidence {
+				return Err(sc_cli::Error::Input(format!(
+					"{}: confidence {} is above {}",
+					at, confidence, max_confidence
+				)))
+			}
+			if !seen.insert((attester.clone(), target.clone())) {
+				return Err(sc_cli::Error::Input(format!("{}: duplicate attestation", at)))
+			}
+			graph.attestations.push((attester, target, confidence as u8));
+		}
+
+		let mut ban_lines = bans;
+		if let Some(path) = &self.bans {
+			ban_lines.extend(
+				fs::read_to_string(path)?
+					.lines()
+					.map(str::trim)
+					.filter(|line| !line.is_empty() && !line.starts_with('#'))
+					.map(String::from),
+			);
+		}
+		for ban in ban_lines {
+			graph.bans.insert(parse_account(&ban, "bans")?);
+		}
+		Ok(graph)
+	}
+}
+
+/// Replaces the account-keyed parts of the social-graph genesis with the
+/// imported graph. Challenges of the base spec are dropped, as their accounts
+/// may not be part of the graph.
+fn seed(social_graph: &mut serde_json::Map<String, serde_json::Value>, graph: &TrustGraph) {
+	social_graph.insert(
+		"attestations".into(),
+		graph
+			.attestations
+			.iter()
+			.map(|(attester, target, confidence)| {
+				serde_json::json!([attester.to_ss58check(), target.to_ss58check(), confidence])
+			})
+			.collect(),
+	);
+	social_graph.insert(
+		"banned".into(),
+		graph
+			.bans
+			.iter()
+			.map(|account| serde_json::Value::from(account.to_ss58check()))
+			.collect(),
+	);
+	social_graph.insert("challenges".into(), serde_json::Value::Array(Vec::new()));
+}
+
+/// The confidence limit set in the social-graph genesis. `GraphParameters`
+/// keeps its field names when serialized, unlike the genesis config itself.
+fn max_confidence(social_graph: &serde_json::Map<String, serde_json::Value>) -> u64 {
+	social_graph
+		.get("parameters")
+		.and_then(|parameters| parameters.get("max_confidence"))
+		.and_then(|max| max.as_u64())
+		.unwrap_or(DEFAULT_MAX_CONFIDENCE)
+}
+
+/// Reads `attester,target,confidence` lines, skipping blank lines, comments
+/// and a header.
+fn read_csv(path: &Path, contents: &str) -> sc_cli::Result<Vec<(String, String, String, u64)>> {
+	let mut edges = Vec::new();
+	for (i, line) in contents.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') || line.starts_with("attester") {
+			continue
+		}
+		let at = format!("{}:{}", path.display(), i + 1);
+		let fields: Vec<_> = line.split(',').map(str::trim).collect();
+		let (attester, target, confidence) = match fields[..] {
+			[attester, target, confidence] => (attester, target, confidence),
+			_ => return Err(sc_cli::Error::Input(format!("{}: expected 3 fields", at))),
+		};
+		let confidence = confidence
+			.parse()
+			.map_err(|_| sc_cli::Error::Input(format!("{}: invalid confidence", at)))?;
+		edges.push((at, attester.into(), target.into(), confidence));
+	}
+	Ok(edges)
+}
+
+fn parse_account(address: &str, at: &str) -> sc_cli::Result<AccountId> {
+	AccountId::from_ss58check(address)
+		.map_err(|e| sc_cli::Error::Input(format!("{}: invalid account {}: {:?}", at, address, e)))
+}
+
+/// Prints the size and shape of the imported graph.
+fn print_summary(graph: &TrustGraph) {
+	let mut in_degree: BTreeMap<&AccountId, u32> = BTreeMap::new();
+	let mut accounts = BTreeSet::new();
+	let mut edges = BTreeSet::new();
+	let mut total_confidence = 0u64;
+	for (attester, target, confidence) in &graph.attestations {
+		accounts.insert(attester);
+		accounts.insert(target);
+		edges.insert((attester, target));
+		*in_degree.entry(target).or_default() += 1;
+		total_confidence += u64::from(*confidence);
+	}
+	let mutual = graph
+		.attestations
+		.iter()
+		.filter(|(attester, target, _)| edges.contains(&(target, attester)))
+		.count() / 2;
+	let unattested = accounts.iter().filter(|account| !in_degree.contains_key(*account)).count();
+
+	eprintln!("Accounts:              {}", accounts.len());
+	eprintln!("Attestations:          {}", graph.attestations.len());
+	eprintln!("Mutual pairs:          {}", mutual);
+	if !graph.attestations.is_empty() {
+		eprintln!(
+			"Average confidence:    {:.2}",
+			total_confidence as f64 / graph.attestations.len() as f64
+		);
+		eprintln!(
+			"Average attestations:  {:.2} per account",
+			graph.attestations.len() as f64 / accounts.len() as f64
+		);
+	}
+	if let Some((account, degree)) = in_degree.iter().max_by_key(|(_, degree)| **degree) {
+		eprintln!("Most attested:         {} ({})", account.to_ss58check(), degree);
+	}
+	eprintln!("Without attestations:  {}", unattested);
+	eprintln!("Banned:                {}", graph.bans.len());
+}
+
+impl CliConfiguration for ImportGraphCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use clap::Parser;
+	use node_template_runtime::SocialGraphConfig;
+	use sp_keyring::AccountKeyring::{Alice, Bob};
+
+	#[test]
+	fn uses_max_confidence_from_spec() {
+		let mut config = SocialGraphConfig::default();
+		config.parameters.max_confidence = 5;
+		let spec = serde_json::json!({ "genesis": { "runtime": { "socialGraph": config } } });
+		let social_graph = spec
+			.pointer("/genesis/runtime/socialGraph")
+			.and_then(|value| value.as_object())
+			.unwrap();
+		let max = max_confidence(social_graph);
+		assert_eq!(max, 5);
+
+		let input = std::env::temp_dir().join(format!("import-graph-{}.csv", std::process::id()));
+		fs::write(
+			&input,
+			format!(
+				"{},{},7\n",
+				Alice.to_account_id().to_ss58check(),
+				Bob.to_account_id().to_ss58check()
+			),
+		)
+		.unwrap();
+		let cmd = ImportGraphCmd::parse_from(["import-graph", input.to_str().unwrap()]);
+		assert!(cmd.read_graph(max).is_err());
+		assert_eq!(cmd.read_graph(DEFAULT_MAX_CONFIDENCE).unwrap().attestations.len(), 1);
+		fs::remove_file(input).unwrap();
+	}
+	#[test]
+	fn seed_drops_base_challenges() {
+		let mut config = SocialGraphConfig::default();
+		config.challenges = vec![(Alice.to_account_id(), Bob.to_account_id())];
+		let mut social_graph = serde_json::to_value(config).unwrap().as_object().unwrap().clone();
+		let graph = TrustGraph {
+			attestations: vec![(Alice.to_account_id(), Bob.to_account_id(), 3)],
+			bans: BTreeSet::new(),
+		};
+		seed(&mut social_graph, &graph);
+		assert_eq!(social_graph["challenges"], serde_json::json!([]));
+		assert_eq!(social_graph["attestations"].as_array().unwrap().len(), 1);
+	}
+}
@@ -0,0 +1,600 @@
+//! Local index of social-graph history.
+//!
+//! The indexer reads the social-graph events of each finalized block and
+//! writes attestations, challenges, votes and bans to an SQLite database so
+//! history queries don't need an archive node. Rows are only ever appended,
+//! each tagged with the block it came from. If indexed blocks are no longer on
+//! the canonical chain, for example after `revert` or after switching the
+//! node's database, every row above the last block both chains share is
+//! deleted and the blocks are indexed again. Events are decoded with the
+//! node's native types; blocks of older runtimes whose events don't decode
+//! are logged and indexed without events.
+
+use crate::service::FullClient;
+use codec::DecodeAll;
+use frame_system::EventRecord;
+use futures::StreamExt;
+use node_template_runtime::{opaque::Block, AccountId, BlockNumber, Event, Hash, Runtime};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use sc_client_api::{BlockchainEvents, StorageProvider};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::twox_128, storage::StorageKey};
+use sp_runtime::generic::BlockId;
+use std::{
+	path::Path,
+	sync::{Arc, Mutex},
+};
+
+/// An event of the social-graph pallet.
+pub type GraphEvent = node_template_runtime::social_graph::Event<Runtime>;
+
+/// Tables of the index. Every table but `blocks` is keyed by the block and
+/// the index of the event within it.
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS blocks (
+		number INTEGER PRIMARY KEY,
+		hash BLOB NOT NULL
+	);
+	CREATE TABLE IF NOT EXISTS attestations (
+		block INTEGER NOT NULL,
+		event INTEGER NOT NULL,
+		target BLOB NOT NULL,
+		attester BLOB NOT NULL,
+		kind TEXT NOT NULL,
+		confidence INTEGER,
+		relationship TEXT,
+		known_for INTEGER,
+		PRIMARY KEY (block, event)
+	);
+	CREATE INDEX IF NOT EXISTS attestations_target ON attestations (target, attester);
+	CREATE TABLE IF NOT EXISTS challenges (
+		block INTEGER NOT NULL,
+		event INTEGER NOT NULL,
+		suspect BLOB NOT NULL,
+		kind TEXT NOT NULL,
+		challenge_id INTEGER,
+		challenger BLOB,
+		final_block INTEGER,
+		tally INTEGER,
+		turnout INTEGER,
+		banned INTEGER,
+		PRIMARY KEY (block, event)
+	);
+	CREATE INDEX IF NOT EXISTS challenges_suspect ON challenges (suspect);
+	CREATE TABLE IF NOT EXISTS votes (
+		block INTEGER NOT NULL,
+		event INTEGER NOT NULL,
+		suspect BLOB NOT NULL,
+		voter BLOB NOT NULL,
+		vote INTEGER,
+		PRIMARY KEY (block, event)
+	);
+	CREATE INDEX IF NOT EXISTS votes_suspect ON votes (suspect, voter);
+	CREATE TABLE IF NOT EXISTS bans (
+		block INTEGER NOT NULL,
+		event INTEGER NOT NULL,
+		account BLOB NOT NULL,
+		banned INTEGER NOT NULL,
+		reason INTEGER,
+		PRIMARY KEY (block, event)
+	);
+	CREATE INDEX IF NOT EXISTS bans_account ON bans (account);
+";
+
+/// Tables holding indexed rows, cleared from a block on when it is re-indexed.
+const TABLES: [&str; 5] = ["blocks", "attestations", "challenges", "votes", "bans"];
+
+/// Errors of the indexer.
+#[derive(Debug)]
+pub enum Error {
+	/// The database failed.
+	Database(rusqlite::Error),
+	/// The client failed to read a block or its state.
+	Client(sp_blockchain::Error),
+	/// A finalized block's hash could not be found.
+	MissingBlock(BlockNumber),
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Error::Database(e) => write!(f, "Indexer database error: {}", e),
+			Error::Client(e) => write!(f, "Indexer client error: {}", e),
+			Error::MissingBlock(n) => write!(f, "Indexer could not read block #{}", n),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+	fn from(e: rusqlite::Error) -> Self {
+		Error::Database(e)
+	}
+}
+
+impl From<sp_blockchain::Error> for Error {
+	fn from(e: sp_blockchain::Error) -> Self {
+		Error::Client(e)
+	}
+}
+
+/// An attestation given or removed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationEntry {
+	/// Block the event happened in
+	pub block: BlockNumber,
+	/// Account attested for
+	pub target: AccountId,
+	/// Account attesting
+	pub attester: AccountId,
+	/// `attested` or `removed`
+	pub kind: String,
+	/// Confidence of the attestation, if it was given
+	pub confidence: Option<u8>,
+	/// How the attester knows the target, if the attestation was given
+	pub relationship: Option<String>,
+	/// Months the attester has known the target, if the attestation was given
+	pub known_for: Option<u16>,
+}
+
+/// A step in a challenge's lifecycle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChallengeEntry {
+	/// Block the event happened in
+	pub block: BlockNumber,
+	/// Account challenged
+	pub suspect: AccountId,
+	/// `created`, `extended`, `resolved`, `pending`, `enacted`, `overturned`,
+	/// `cancelled`, `forceResolved` or `forceCancelled`
+	pub kind: String,
+	/// ID of the challenge, on `created` and `resolved`
+	pub challenge_id: Option<u32>,
+	/// Account that opened the challenge, on `created`
+	pub challenger: Option<AccountId>,
+	/// Block voting ends or the verdict is enacted
+	pub final_block: Option<BlockNumber>,
+	/// Sum of the votes, on `resolved`
+	pub tally: Option<i32>,
+	/// Number of votes, on `resolved`
+	pub turnout: Option<u32>,
+	/// Whether the verdict bans the suspect
+	pub banned: Option<bool>,
+}
+
+/// A vote cast or withdrawn.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteEntry {
+	/// Block the event happened in
+	pub block: BlockNumber,
+	/// Account challenged
+	pub suspect: AccountId,
+	/// Account voting
+	pub voter: AccountId,
+	/// The vote, or `None` if it was withdrawn
+	pub vote: Option<i8>,
+}
+
+/// A ban or an unban.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BanEntry {
+	/// Block the event happened in
+	pub block: BlockNumber,
+	/// Account banned or unbanned
+	pub account: AccountId,
+	/// Whether the account was banned or unbanned
+	pub banned: bool,
+	/// Reason code an admin gave, if an admin did it
+	pub reason: Option<u16>,
+}
+
+/// The SQLite index of social-graph history.
+pub struct Indexer {
+	db: Mutex<Connection>,
+}
+
+impl Indexer {
+	/// Opens the database at `path`, creating it if needed.
+	pub fn open(path: &Path) -> Result<Self, Error> {
+		let db = Connection::open(path)?;
+		db.execute_batch(SCHEMA)?;
+		Ok(Self { db: Mutex::new(db) })
+	}
+
+	/// Indexes every finalized block not indexed yet, first rolling back
+	/// blocks that are no longer canonical.
+	pub fn sync(&self, client: &FullClient) -> Result<(), Error> {
+		// Walk the indexed blocks down from the top to the common ancestor, the
+		// highest one that is still canonical, and roll back everything above it.
+		let next = {
+			let mut db = self.db.lock().expect("Indexer lock poisoned");
+			let mut below = BlockNumber::MAX;
+			let next = loop {
+				let indexed: Option<(BlockNumber, Vec<u8>)> = db
+					.query_row(
+						"SELECT number, hash FROM blocks WHERE number < ?1
+						ORDER BY number DESC LIMIT 1",
+						[below],
+						|row| Ok((row.get(0)?, row.get(1)?)),
+					)
+					.optional()?;
+				let (number, hash) = match indexed {
+					Some(indexed) => indexed,
+					None => break 0,
+				};
+				if client.hash(number)?.map(|h| h.as_bytes().to_vec()) == Some(hash) {
+					break number + 1
+				}
+				below = number;
+			};
+			let tx = db.transaction()?;
+			for table in TABLES {
+				let column = if table == "blocks" { "number" } else { "block" };
+				tx.execute(&format!("DELETE FROM {} WHERE {} >= ?1", table, column), [next])?;
+			}
+			tx.commit()?;
+			next
+		};
+
+		// Index the rest block by block, so a restart picks up where it stopped
+		// and history queries aren't held up while catching up.
+		let finalized = client.info().finalized_number;
+		for number in next..=finalized {
+			let hash = client.hash(number)?.ok_or(Error::MissingBlock(number))?;
+			let mut db = self.db.lock().expect("Indexer lock poisoned");
+			let tx = db.transaction()?;
+			index_block(&tx, client, number, hash)?;
+			tx.execute(
+				"INSERT INTO blocks (number, hash) VALUES (?1, ?2)",
+				params![number, hash.as_bytes()],
+			)?;
+			tx.commit()?;
+		}
+		Ok(())
+	}
+
+	/// Attestations given to `target` and removed from it, optionally only
+	/// those of `attester`, oldest first.
+	pub fn attestation_history(
+		&self,
+		target: &AccountId,
+		attester: Option<&AccountId>,
+	) -> Result<Vec<AttestationEntry>, Error> {
+		let db = self.db.lock().expect("Indexer lock poisoned");
+		let mut stmt = db.prepare(
+			"SELECT block, target, attester, kind, confidence, relationship, known_for
+			FROM attestations WHERE target = ?1 AND (?2 IS NULL OR attester = ?2)
+			ORDER BY block, event",
+		)?;
+		let entries = stmt
+			.query_map(params![account_bytes(target), attester.map(account_bytes)], |row| {
+				Ok(AttestationEntry {
+					block: row.get(0)?,
+					target: account(row.get(1)?),
+					attester: account(row.get(2)?),
+					kind: row.get(3)?,
+					confidence: row.get(4)?,
+					relationship: row.get(5)?,
+					known_for: row.get(6)?,
+				})
+			})?
+			.collect::<Result<_, _>>()?;
+		Ok(entries)
+	}
+
+	/// Every step of every challenge of `suspect`, oldest first.
+	pub fn challenge_history(&self, suspect: &AccountId) -> Result<Vec<ChallengeEntry>, Error> {
+		let db = self.db.lock().expect("Indexer lock poisoned");
+		let mut stmt = db.prepare(
+			"SELECT block, suspect, kind, challenge_id, challenger, final_block, tally, turnout, banned
+			FROM challenges WHERE suspect = ?1 ORDER BY block, event",
+		)?;
+		let entries = stmt
+			.query_map(params![account_bytes(suspect)], |row| {
+				Ok(ChallengeEntry {
+					block: row.get(0)?,
+					suspect: account(row.get(1)?),
+					kind: row.get(2)?,
+					challenge_id: row.get(3)?,
+					challenger: row.get::<_, Option<Vec<u8>>>(4)?.map(account),
+					final_block: row.get(5)?,
+					tally: row.get(6)?,
+					turnout: row.get(7)?,
+					banned: row.get(8)?,
+				})
+			})?
+			.collect::<Result<_, _>>()?;
+		Ok(entries)
+	}
+
+	/// Votes cast on challenges of `suspect` and withdrawn, optionally only
+	/// those of `voter`, oldest first.
+	pub fn vote_history(
+		&self,
+		suspect: &AccountId,
+		voter: Option<&AccountId>,
+	) -> Result<Vec<VoteEntry>, Error> {
+		let db = self.db.lock().expect("Indexer lock poisoned");
+		let mut stmt = db.prepare(
+			"SELECT block, suspect, voter, vote FROM votes
+			WHERE suspect = ?1 AND (?2 IS NULL OR voter = ?2) ORDER BY block, event",
+		)?;
+		let entries = stmt
+			.query_map(params![account_bytes(suspect), voter.map(account_bytes)], |row| {
+				Ok(VoteEntry {
+					block: row.get(0)?,
+					suspect: account(row.get(1)?),
+					voter: account(row.get(2)?),
+					vote: row.get(3)?,
+				})
+			})?
+			.collect::<Result<_, _>>()?;
+		Ok(entries)
+	}
+
+	/// Bans and unbans of `account`, oldest first.
+	pub fn ban_history(&self, account_id: &AccountId) -> Result<Vec<BanEntry>, Error> {
+		let db = self.db.lock().expect("Indexer lock poisoned");
+		let mut stmt = db.prepare(
+			"SELECT block, account, banned, reason FROM bans WHERE account = ?1 ORDER BY block, event",
+		)?;
+		let entries = stmt
+			.query_map(params![account_bytes(account_id)], |row| {
+				Ok(BanEntry {
+					block: row.get(0)?,
+					account: account(row.get(1)?),
+					banned: row.get(2)?,
+					reason: row.get(3)?,
+				})
+			})?
+			.collect::<Result<_, _>>()?;
+		Ok(entries)
+	}
+}
+
+/// Keeps the index in step with finality until the node shuts down.
+pub async fn run(client: Arc<FullClient>, indexer: Arc<Indexer>) {
+	let mut finality = client.finality_notification_stream();
+	loop {
+		if let Err(e) = indexer.sync(&client) {
+			log::warn!(target: "social-graph", "{}", e);
+		}
+		if finality.next().await.is_none() {
+			break
+		}
+	}
+}
+
+/// Reads the social-graph events of a block along with their index among
+/// all of the block's events. Events are decoded with the node's native
+/// types, so `None` is returned for blocks of a runtime whose events were laid
+/// out differently.
+pub fn graph_events<B, C>(
+	client: &C,
+	hash: Hash,
+) -> sp_blockchain::Result<Option<Vec<(u32, GraphEvent)>>>
+where
+	B: sc_client_api::Backend<Block>,
+	C: StorageProvider<Block, B>,
+{
+	let key = StorageKey([twox_128(b"System"), twox_128(b"Events")].concat());
+	let records = match client.storage(&BlockId::Hash(hash), &key)? {
+		Some(data) => match Vec::<EventRecord<Event, Hash>>::decode_all(&mut &data.0[..]) {
+			Ok(records) => records,
+			Err(_) => return Ok(None),
+		},
+		None => return Ok(Some(Vec::new())),
+	};
+	Ok(Some(
+		records
+			.into_iter()
+			.enumerate()
+			.filter_map(|(index, record)| match record.event {
+				Event::SocialGraph(event) => Some((index as u32, event)),
+				_ => None,
+			})
+			.collect(),
+	))
+}
+
+/// Writes the social-graph events of block `number` to the index. Blocks
+/// whose events can't be decoded are logged and indexed without events, so
+/// history from older runtimes doesn't stop the index.
+fn index_block(
+	tx: &Transaction,
+	client: &FullClient,
+	number: BlockNumber,
+	hash: Hash,
+) -> Result<(), Error> {
+	let events = graph_events(client, hash)?.unwrap_or_else(|| {
+		log::warn!(
+			target: "social-graph",
+			"Skipping events of block #{} ({}), they were produced by an incompatible runtime",
+			number,
+			hash
+		);
+		Vec::new()
+	});
+	for (index, event) in events {
+		match event {
+			GraphEvent::Attested(attester, target, attestation) => {
+				tx.execute(
+					"INSERT INTO attestations
+					(block, event, target, attester, kind, confidence, relationship, known_for)
+					VALUES (?1, ?2, ?3, ?4, 'attested', ?5, ?6, ?7)",
+					params![
+						number,
+						index,
+						account_bytes(&target),
+						account_bytes(&attester),
+						attestation.confidence,
+						format!("{:?}", attestation.relationship),
+						attestation.known_for,
+					],
+				)?;
+			},
+			GraphEvent::AttestationForceRemoved(target, attester) |
+			GraphEvent::AttestationRemoved(target, attester) => {
+				tx.execute(
+					"INSERT INTO attestations (block, event, target, attester, kind)
+					VALUES (?1, ?2, ?3, ?4, 'removed')",
+					params![number, index, account_bytes(&target), account_bytes(&attester)],
+				)?;
+			},
+			GraphEvent::ChallengeCreated(id, challenger, suspect, final_block) => {
+				tx.execute(
+					"INSERT INTO challenges
+					(block, event, suspect, kind, challenge_id, challenger, final_block)
+					VALUES (?1, ?2, ?3, 'created', ?4, ?5, ?6)",
+					params![
+						number,
+						index,
+						account_bytes(&suspect),
+						id,
+						account_bytes(&challenger),
+						final_block,
+					],
+				)?;
+			},
+			GraphEvent::ChallengeResolved(id, suspect, outcome) => {
+				tx.execute(
+					"INSERT INTO challenges
+					(block, event, suspect, kind, challenge_id, tally, turnout, banned)
+					VALUES (?1, ?2, ?3, 'resolved', ?4, ?5, ?6, ?7)",
+					params![
+						number,
+						index,
+						account_bytes(&suspect),
+						id,
+						outcome.tally,
+						outcome.turnout,
+						outcome.banned,
+					],
+				)?;
+			},
+			GraphEvent::ChallengeExtended(suspect, final_block) => insert_challenge_step(
+				tx,
+				number,
+				index,
+				&suspect,
+				"extended",
+				Some(final_block),
+				None,
+			)?,
+			GraphEvent::VerdictPending(suspect, ban, final_block) => insert_challenge_step(
+				tx,
+				number,
+				index,
+				&suspect,
+				"pending",
+				Some(final_block),
+				Some(ban),
+			)?,
+			GraphEvent::VerdictEnacted(suspect, ban) => {
+				insert_challenge_step(tx, number, index, &suspect, "enacted", None, Some(ban))?;
+				if ban {
+					insert_ban(tx, number, index, &suspect, true, None)?;
+				}
+			},
+			GraphEvent::VerdictOverturned(suspect, ban) => {
+				insert_challenge_step(tx, number, index, &suspect, "overturned", None, Some(ban))?;
+				if ban {
+					insert_ban(tx, number, index, &suspect, true, None)?;
+				}
+			},
+			GraphEvent::ChallengeForceResolved(suspect, ban) => {
+				insert_challenge_step(
+					tx,
+					number,
+					index,
+					&suspect,
+					"forceResolved",
+					None,
+					Some(ban),
+				)?;
+				if ban {
+					insert_ban(tx, number, index, &suspect, true, None)?;
+				}
+			},
+			GraphEvent::ChallengeCancelled(suspect, _) =>
+				insert_challenge_step(tx, number, index, &suspect, "cancelled", None, None)?,
+			GraphEvent::ChallengeForceCancelled(suspect) =>
+				insert_challenge_step(tx, number, index, &suspect, "forceCancelled", None, None)?,
+			GraphEvent::VoteSubmitted(voter, suspect, vote) =>
+				insert_vote(tx, number, index, &suspect, &voter, Some(vote))?,
+			GraphEvent::VoteWithdrawn(voter, suspect) =>
+				insert_vote(tx, number, index, &suspect, &voter, None)?,
+			GraphEvent::ForceBanned(account, reason) =>
+				insert_ban(tx, number, index, &account, true, Some(reason))?,
+			GraphEvent::ForceUnbanned(account, reason) =>
+				insert_ban(tx, number, index, &account, false, Some(reason))?,
+			_ => {},
+		}
+	}
+	Ok(())
+}
+
+/// Writes a challenge event without an ID or votes.
+fn insert_challenge_step(
+	tx: &Transaction,
+	number: BlockNumber,
+	index: u32,
+	suspect: &AccountId,
+	kind: &str,
+	final_block: Option<BlockNumber>,
+	banned: Option<bool>,
+) -> Result<(), Error> {
+	tx.execute(
+		"INSERT INTO challenges (block, event, suspect, kind, final_block, banned)
+		VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+		params![number, index, account_bytes(suspect), kind, final_block, banned],
+	)?;
+	Ok(())
+}
+
+/// Writes a vote, or its withdrawal if `vote` is `None`.
+fn insert_vote(
+	tx: &Transaction,
+	number: BlockNumber,
+	index: u32,
+	suspect: &AccountId,
+	voter: &AccountId,
+	vote: Option<i8>,
+) -> Result<(), Error> {
+	tx.execute(
+		"INSERT INTO votes (block, event, suspect, voter, vote) VALUES (?1, ?2, ?3, ?4, ?5)",
+		params![number, index, account_bytes(suspect), account_bytes(voter), vote],
+	)?;
+	Ok(())
+}
+
+/// Writes a ban or an unban.
+fn insert_ban(
+	tx: &Transaction,
+	number: BlockNumber,
+	index: u32,
+	account: &AccountId,
+	banned: bool,
+	reason: Option<u16>,
+) -> Result<(), Error> {
+	tx.execute(
+		"INSERT INTO bans (block, event, account, banned, reason) VALUES (?1, ?2, ?3, ?4, ?5)",
+		params![number, index, account_bytes(account), banned, reason],
+	)?;
+	Ok(())
+}
+
+fn account_bytes(account: &AccountId) -> Vec<u8> {
+	AsRef::<[u8]>::as_ref(account).to_vec()
+}
+
+fn account(bytes: Vec<u8>) -> AccountId {
+	let mut raw = [0u8; 32];
+	raw.copy_from_slice(&bytes);
+	AccountId::new(raw)
+}
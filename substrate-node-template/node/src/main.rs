@@ -7,6 +7,9 @@ mod service;
 mod cli;
 mod command;
 mod command_helper;
+mod export_graph;
+mod import_graph;
+mod indexer;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
@@ -5,14 +5,35 @@
 
 #![warn(missing_docs)]
 
-use std::sync::Arc;
+use std::{marker::PhantomData, sync::Arc};
 
-use jsonrpsee::RpcModule;
-use node_template_runtime::{opaque::Block, AccountId, Balance, Index};
+use crate::indexer::{
+	graph_events, AttestationEntry, BanEntry, ChallengeEntry, GraphEvent, Indexer, VoteEntry,
+};
+use futures::{
+	channel::{mpsc, oneshot},
+	stream, FutureExt, SinkExt, Stream, StreamExt,
+};
+use jsonrpsee::{
+	core::{async_trait, Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::{
+		error::{CallError, ErrorObject},
+		SubscriptionResult,
+	},
+	RpcModule, SubscriptionSink,
+};
+use node_template_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash, Index};
+use sc_client_api::{BlockchainEvents, StorageProvider};
+use sc_consensus_manual_seal::{CreatedBlock, EngineCommand};
+use sc_rpc::SubscriptionTaskExecutor;
 use sc_transaction_pool_api::TransactionPool;
+use serde::Serialize;
+use social_graph_runtime_api::SocialGraphApi;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_runtime::generic::BlockId;
 
 pub use sc_rpc_api::DenyUnsafe;
 
@@ -24,29 +45,482 @@ pub struct FullDeps<C, P> {
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Social-graph index, if the node runs one
+	pub indexer: Option<Arc<Indexer>>,
+	/// Executor for subscription tasks
+	pub subscription_executor: SubscriptionTaskExecutor,
+	/// Channel to the manual-seal task, if blocks are sealed on demand
+	pub command_sink: Option<mpsc::Sender<EngineCommand<Hash>>>,
+}
+
+/// Error code of a failed history query.
+const INDEXER_ERROR: i32 = 1;
+
+/// History of the social graph, served from the node's local index.
+#[rpc(server)]
+pub trait SocialGraphHistoryApi {
+	/// Attestations given to and removed from `target`, optionally only
+	/// those of `attester`, oldest first.
+	#[method(name = "socialGraph_attestationHistory")]
+	fn attestation_history(
+		&self,
+		target: AccountId,
+		attester: Option<AccountId>,
+	) -> RpcResult<Vec<AttestationEntry>>;
+
+	/// Every step of every challenge of `suspect`, oldest first.
+	#[method(name = "socialGraph_challengeHistory")]
+	fn challenge_history(&self, suspect: AccountId) -> RpcResult<Vec<ChallengeEntry>>;
+
+	/// Votes cast on challenges of `suspect` and withdrawn, optionally only
+	/// those of `voter`, oldest first.
+	#[method(name = "socialGraph_voteHistory")]
+	fn vote_history(
+		&self,
+		suspect: AccountId,
+		voter: Option<AccountId>,
+	) -> RpcResult<Vec<VoteEntry>>;
+
+	/// Bans and unbans of `account`, oldest first.
+	#[method(name = "socialGraph_banHistory")]
+	fn ban_history(&self, account: AccountId) -> RpcResult<Vec<BanEntry>>;
+}
+
+/// Serves `SocialGraphHistoryApi` from the indexer.
+pub struct SocialGraphHistory {
+	indexer: Arc<Indexer>,
+}
+
+impl SocialGraphHistory {
+	/// Create new `SocialGraphHistory` reading from the given index.
+	pub fn new(indexer: Arc<Indexer>) -> Self {
+		Self { indexer }
+	}
+}
+
+fn history_error(e: crate::indexer::Error) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(
+		INDEXER_ERROR,
+		"Unable to query social graph history.",
+		Some(e.to_string()),
+	))
+	.into()
+}
+
+impl SocialGraphHistoryApiServer for SocialGraphHistory {
+	fn attestation_history(
+		&self,
+		target: AccountId,
+		attester: Option<AccountId>,
+	) -> RpcResult<Vec<AttestationEntry>> {
+		self.indexer
+			.attestation_history(&target, attester.as_ref())
+			.map_err(history_error)
+	}
+
+	fn challenge_history(&self, suspect: AccountId) -> RpcResult<Vec<ChallengeEntry>> {
+		self.indexer.challenge_history(&suspect).map_err(history_error)
+	}
+
+	fn vote_history(
+		&self,
+		suspect: AccountId,
+		voter: Option<AccountId>,
+	) -> RpcResult<Vec<VoteEntry>> {
+		self.indexer.vote_history(&suspect, voter.as_ref()).map_err(history_error)
+	}
+
+	fn ban_history(&self, account: AccountId) -> RpcResult<Vec<BanEntry>> {
+		self.indexer.ban_history(&account).map_err(history_error)
+	}
+}
+
+/// Shortcuts over the manual-seal RPCs, to move a development chain forward.
+#[rpc(server)]
+pub trait SealingApi {
+	/// Seals `count` blocks one after the other, even without transactions,
+	/// and finalizes each of them if `finalize` is set.
+	#[method(name = "engine_createBlocks")]
+	async fn create_blocks(&self, count: u32, finalize: bool)
+		-> RpcResult<Vec<CreatedBlock<Hash>>>;
+}
+
+/// Serves `SealingApi` by sending commands to the manual-seal task.
+pub struct SealingHelper {
+	command_sink: mpsc::Sender<EngineCommand<Hash>>,
+}
+
+impl SealingHelper {
+	/// Create new `SealingHelper` sending commands through the given channel.
+	pub fn new(command_sink: mpsc::Sender<EngineCommand<Hash>>) -> Self {
+		Self { command_sink }
+	}
+}
+
+#[async_trait]
+impl SealingApiServer for SealingHelper {
+	async fn create_blocks(
+		&self,
+		count: u32,
+		finalize: bool,
+	) -> RpcResult<Vec<CreatedBlock<Hash>>> {
+		let mut sink = self.command_sink.clone();
+		let mut blocks = Vec::new();
+		for _ in 0..count {
+			let (sender, receiver) = oneshot::channel();
+			sink.send(EngineCommand::SealNewBlock {
+				create_empty: true,
+				finalize,
+				parent_hash: None,
+				sender: Some(sender),
+			})
+			.await
+			.map_err(JsonRpseeError::to_call_error)?;
+			let block = receiver.await.map_err(JsonRpseeError::to_call_error)??;
+			blocks.push(block);
+		}
+		Ok(blocks)
+	}
+}
+
+/// Whether a notification comes from a new best block or a finalized one.
+/// Notifications of a best block may be followed by the same notifications
+/// once the block is finalized, or by none if it is re-orged out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BlockStatus {
+	/// The block became the new best block
+	Best,
+	/// The block was finalized
+	Finalized,
+}
+
+/// A social-graph notification pushed to a subscriber.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification<E> {
+	/// Hash of the block the event happened in
+	pub block: Hash,
+	/// Whether the block is best or finalized
+	pub status: BlockStatus,
+	/// What happened
+	pub event: E,
+}
+
+/// Something that happened to a subscribed account.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AccountEvent {
+	/// The account gained an attestation
+	AttestationReceived { attester: AccountId, confidence: u8 },
+	/// An attestation for the account was removed
+	AttestationRemoved { attester: AccountId },
+	/// The account was challenged
+	#[serde(rename_all = "camelCase")]
+	Challenged { challenge_id: u32, challenger: AccountId, final_block: BlockNumber },
+	/// A vote was cast on the account's challenge
+	VoteReceived { voter: AccountId },
+	/// The account became valid or stopped being valid
+	ValidityChanged { valid: bool },
+	/// The account was banned
+	Banned,
+	/// The account's ban was lifted
+	Unbanned,
+}
+
+/// Something that happened to a challenge.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ChallengeEvent {
+	/// A challenge was opened
+	#[serde(rename_all = "camelCase")]
+	Created {
+		challenge_id: u32,
+		challenger: AccountId,
+		suspect: AccountId,
+		final_block: BlockNumber,
+	},
+	/// A vote was cast
+	Voted { suspect: AccountId, voter: AccountId },
+	/// A vote was withdrawn
+	VoteWithdrawn { suspect: AccountId, voter: AccountId },
+	/// The challenger extended the challenge
+	#[serde(rename_all = "camelCase")]
+	Extended { suspect: AccountId, final_block: BlockNumber },
+	/// The vote reached a verdict
+	#[serde(rename_all = "camelCase")]
+	Resolved { challenge_id: u32, suspect: AccountId, tally: i32, turnout: u32, banned: bool },
+	/// The verdict waits for the council's veto period
+	#[serde(rename_all = "camelCase")]
+	VerdictPending { suspect: AccountId, banned: bool, final_block: BlockNumber },
+	/// The verdict took effect
+	VerdictEnacted { suspect: AccountId, banned: bool },
+	/// The council overturned the verdict
+	VerdictOverturned { suspect: AccountId, banned: bool },
+	/// An admin resolved the challenge with the given verdict
+	ForceResolved { suspect: AccountId, banned: bool },
+	/// The challenge was cancelled without a verdict
+	Cancelled { suspect: AccountId },
+}
+
+impl ChallengeEvent {
+	/// Whether `account` is the suspect, the challenger or a voter.
+	fn involves(&self, account: &AccountId) -> bool {
+		match self {
+			ChallengeEvent::Created { challenger, suspect, .. } =>
+				challenger == account || suspect == account,
+			ChallengeEvent::Voted { suspect, voter } |
+			ChallengeEvent::VoteWithdrawn { suspect, voter } =>
+				suspect == account || voter == account,
+			ChallengeEvent::Extended { suspect, .. } |
+			ChallengeEvent::Resolved { suspect, .. } |
+			ChallengeEvent::VerdictPending { suspect, .. } |
+			ChallengeEvent::VerdictEnacted { suspect, .. } |
+			ChallengeEvent::VerdictOverturned { suspect, .. } |
+			ChallengeEvent::ForceResolved { suspect, .. } |
+			ChallengeEvent::Cancelled { suspect } => suspect == account,
+		}
+	}
+}
+
+/// Push notifications of social-graph activity.
+#[rpc(server)]
+pub trait SocialGraphSubscriptionApi {
+	/// Notifies of attestations gained and lost by `account`, challenges of
+	/// it and votes on them, and changes of its validity or ban.
+	#[subscription(
+		name = "socialGraph_subscribeAccount" => "socialGraph_account",
+		unsubscribe = "socialGraph_unsubscribeAccount",
+		item = Notification<AccountEvent>
+	)]
+	fn subscribe_account(&self, account: AccountId);
+
+	/// Notifies of challenges being opened, voted on and closed, optionally
+	/// only those `account` takes part in.
+	#[subscription(
+		name = "socialGraph_subscribeChallenges" => "socialGraph_challenge",
+		unsubscribe = "socialGraph_unsubscribeChallenges",
+		item = Notification<ChallengeEvent>
+	)]
+	fn subscribe_challenges(&self, account: Option<AccountId>);
+}
+
+/// Serves `SocialGraphSubscriptionApi` from the client's block notifications.
+pub struct SocialGraphSubscriptions<C, B> {
+	client: Arc<C>,
+	executor: SubscriptionTaskExecutor,
+	_backend: PhantomData<fn() -> B>,
+}
+
+impl<C, B> SocialGraphSubscriptions<C, B> {
+	/// Create new `SocialGraphSubscriptions` with the given client and executor.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		Self { client, executor, _backend: PhantomData }
+	}
+}
+
+/// New best blocks and finalized blocks, including those finalized implicitly
+/// by finalizing a descendant.
+fn block_stream<C>(client: &C) -> impl Stream<Item = (Hash, BlockStatus)>
+where
+	C: BlockchainEvents<Block>,
+{
+	let best = client.import_notification_stream().filter_map(|notification| {
+		futures::future::ready(
+			notification.is_new_best.then(|| (notification.hash, BlockStatus::Best)),
+		)
+	});
+	let finalized = client.finality_notification_stream().flat_map(|notification| {
+		let hashes: Vec<_> = notification
+			.tree_route
+			.iter()
+			.cloned()
+			.chain(std::iter::once(notification.hash))
+			.map(|hash| (hash, BlockStatus::Finalized))
+			.collect();
+		stream::iter(hashes)
+	});
+	stream::select(best, finalized)
+}
+
+/// Social-graph events of a block, logging and skipping blocks whose events
+/// can't be read.
+fn block_graph_events<B, C>(client: &C, hash: Hash) -> Vec<GraphEvent>
+where
+	B: sc_client_api::Backend<Block>,
+	C: StorageProvider<Block, B>,
+{
+	match graph_events(client, hash) {
+		Ok(Some(events)) => events.into_iter().map(|(_, event)| event).collect(),
+		Ok(None) => {
+			log::warn!(target: "social-graph", "Undecodable events in {}", hash);
+			Vec::new()
+		},
+		Err(e) => {
+			log::warn!(target: "social-graph", "Failed to read events of {}: {}", hash, e);
+			Vec::new()
+		},
+	}
+}
+
+/// What a social-graph event means for `account`, if anything.
+fn account_event(event: GraphEvent, account: &AccountId) -> Option<AccountEvent> {
+	match event {
+		GraphEvent::Attested(attester, target, attestation) if target == *account => {
+			let confidence = attestation.confidence;
+			Some(AccountEvent::AttestationReceived { attester, confidence })
+		},
+		GraphEvent::AttestationForceRemoved(target, attester) |
+		GraphEvent::AttestationRemoved(target, attester)
+			if target == *account =>
+			Some(AccountEvent::AttestationRemoved { attester }),
+		GraphEvent::ChallengeCreated(challenge_id, challenger, suspect, final_block)
+			if suspect == *account =>
+			Some(AccountEvent::Challenged { challenge_id, challenger, final_block }),
+		GraphEvent::VoteSubmitted(voter, suspect, _) if suspect == *account =>
+			Some(AccountEvent::VoteReceived { voter }),
+		GraphEvent::ForceBanned(banned, _) |
+		GraphEvent::VerdictEnacted(banned, true) |
+		GraphEvent::VerdictOverturned(banned, true) |
+		GraphEvent::ChallengeForceResolved(banned, true)
+			if banned == *account =>
+			Some(AccountEvent::Banned),
+		GraphEvent::ForceUnbanned(unbanned, _) if unbanned == *account =>
+			Some(AccountEvent::Unbanned),
+		_ => None,
+	}
+}
+
+/// The social-graph event as a challenge event, if it is one.
+fn challenge_event(event: GraphEvent) -> Option<ChallengeEvent> {
+	Some(match event {
+		GraphEvent::ChallengeCreated(challenge_id, challenger, suspect, final_block) =>
+			ChallengeEvent::Created { challenge_id, challenger, suspect, final_block },
+		GraphEvent::VoteSubmitted(voter, suspect, _) => ChallengeEvent::Voted { suspect, voter },
+		GraphEvent::VoteWithdrawn(voter, suspect) =>
+			ChallengeEvent::VoteWithdrawn { suspect, voter },
+		GraphEvent::ChallengeExtended(suspect, final_block) =>
+			ChallengeEvent::Extended { suspect, final_block },
+		GraphEvent::ChallengeResolved(challenge_id, suspect, outcome) => ChallengeEvent::Resolved {
+			challenge_id,
+			suspect,
+			tally: outcome.tally,
+			turnout: outcome.turnout,
+			banned: outcome.banned,
+		},
+		GraphEvent::VerdictPending(suspect, banned, final_block) =>
+			ChallengeEvent::VerdictPending { suspect, banned, final_block },
+		GraphEvent::VerdictEnacted(suspect, banned) =>
+			ChallengeEvent::VerdictEnacted { suspect, banned },
+		GraphEvent::VerdictOverturned(suspect, banned) =>
+			ChallengeEvent::VerdictOverturned { suspect, banned },
+		GraphEvent::ChallengeForceResolved(suspect, banned) =>
+			ChallengeEvent::ForceResolved { suspect, banned },
+		GraphEvent::ChallengeCancelled(suspect, _) | GraphEvent::ChallengeForceCancelled(suspect) =>
+			ChallengeEvent::Cancelled { suspect },
+		_ => return None,
+	})
+}
+
+impl<C, B> SocialGraphSubscriptionApiServer for SocialGraphSubscriptions<C, B>
+where
+	C: ProvideRuntimeApi<Block> + BlockchainEvents<Block> + StorageProvider<Block, B>,
+	C: Send + Sync + 'static,
+	C::Api: SocialGraphApi<Block, AccountId, BlockNumber>,
+	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
+{
+	fn subscribe_account(&self, sink: SubscriptionSink, account: AccountId) -> SubscriptionResult {
+		let client = self.client.clone();
+		// Validity has no event, so it is compared with the last best and the
+		// last finalized block seen. The first block of each only sets the baseline.
+		let mut last_valid = [None, None];
+		let notifications = block_stream(&*self.client).flat_map(move |(hash, status)| {
+			let mut events: Vec<_> = block_graph_events(&*client, hash)
+				.into_iter()
+				.filter_map(|event| account_event(event, &account))
+				.collect();
+			if let Ok(valid) = client.runtime_api().is_valid(&BlockId::Hash(hash), account.clone())
+			{
+				let last = &mut last_valid[status as usize];
+				if last.map_or(false, |last| last != valid) {
+					events.push(AccountEvent::ValidityChanged { valid });
+				}
+				*last = Some(valid);
+			}
+			stream::iter(
+				events.into_iter().map(move |event| Notification { block: hash, status, event }),
+			)
+		});
+
+		let fut = async move {
+			sink.pipe_from_stream(notifications.boxed()).await;
+		};
+		self.executor.spawn("social-graph-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
+
+	fn subscribe_challenges(
+		&self,
+		sink: SubscriptionSink,
+		account: Option<AccountId>,
+	) -> SubscriptionResult {
+		let client = self.client.clone();
+		let notifications = block_stream(&*self.client).flat_map(move |(hash, status)| {
+			let events: Vec<_> = block_graph_events(&*client, hash)
+				.into_iter()
+				.filter_map(challenge_event)
+				.filter(|event| account.as_ref().map_or(true, |account| event.involves(account)))
+				.collect();
+			stream::iter(
+				events.into_iter().map(move |event| Notification { block: hash, status, event }),
+			)
+		});
+
+		let fut = async move {
+			sink.pipe_from_stream(notifications.boxed()).await;
+		};
+		self.executor.spawn("social-graph-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P>(
+pub fn create_full<C, P, B>(
 	deps: FullDeps<C, P>,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: BlockchainEvents<Block> + StorageProvider<Block, B>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: SocialGraphApi<Block, AccountId, BlockNumber>,
 	C::Api: BlockBuilder<Block>,
+	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 	P: TransactionPool + 'static,
 {
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 	use substrate_frame_rpc_system::{System, SystemApiServer};
 
 	let mut module = RpcModule::new(());
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, pool, deny_unsafe, indexer, subscription_executor, command_sink } =
+		deps;
 
 	module.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
-	module.merge(TransactionPayment::new(client).into_rpc())?;
+	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	module.merge(
+		SocialGraphSubscriptions::<C, B>::new(client, subscription_executor).into_rpc(),
+	)?;
+	if let Some(indexer) = indexer {
+		module.merge(SocialGraphHistory::new(indexer).into_rpc())?;
+	}
+	if let Some(command_sink) = command_sink {
+		use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApiServer};
+
+		module.merge(ManualSeal::new(command_sink.clone()).into_rpc())?;
+		module.merge(SealingHelper::new(command_sink).into_rpc())?;
+	}
 
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
@@ -1,15 +1,31 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use node_template_runtime::{self, opaque::Block, RuntimeApi};
-use sc_client_api::{BlockBackend, ExecutorProvider};
+use futures::{channel::mpsc, stream, StreamExt};
+use node_template_runtime::{self, opaque::Block, BlockNumber, RuntimeApi, DAYS};
+use sc_client_api::{BlockBackend, BlockchainEvents, ExecutorProvider};
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
+use sc_consensus_manual_seal::{
+	consensus::{aura::AuraConsensusDataProvider, timestamp::SlotTimestampProvider},
+	EngineCommand, ManualSealParams,
+};
 pub use sc_executor::NativeElseWasmExecutor;
 use sc_finality_grandpa::SharedVoterState;
 use sc_keystore::LocalKeystore;
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryWorker};
+use sc_transaction_pool_api::TransactionPool;
+use social_graph_runtime_api::{GraphStats, SocialGraphApi};
+use sp_api::ProvideRuntimeApi;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
-use std::{sync::Arc, time::Duration};
+use sp_runtime::generic::BlockId;
+use std::{
+	path::PathBuf,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use substrate_prometheus_endpoint::{
+	register, Gauge, GaugeVec, Opts, PrometheusError, Registry, F64, U64,
+};
 
 // Our native executor instance.
 pub struct ExecutorDispatch;
@@ -31,6 +47,15 @@ impl sc_executor::NativeExecutionDispatch for ExecutorDispatch {
 	}
 }
 
+/// How blocks are authored when Aura and GRANDPA are replaced by manual sealing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum Sealing {
+	/// Seal blocks only when asked to over RPC
+	Manual,
+	/// Also seal and finalize a block for each new transaction
+	Instant,
+}
+
 pub(crate) type FullClient =
 	sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
 type FullBackend = sc_service::TFullBackend<Block>;
@@ -157,7 +182,11 @@ fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_full(
+	mut config: Configuration,
+	indexer_path: Option<PathBuf>,
+	sealing: Option<Sealing>,
+) -> Result<TaskManager, ServiceError> {
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -221,13 +250,31 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	let indexer = match indexer_path {
+		Some(path) => Some(Arc::new(
+			crate::indexer::Indexer::open(&path).map_err(|e| ServiceError::Other(e.to_string()))?,
+		)),
+		None => None,
+	};
+
+	// Commands of the manual-seal RPCs, only served when sealing on demand.
+	let (command_sink, commands_stream) = mpsc::channel(1024);
+	let command_sink = sealing.map(|_| command_sink);
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
-
-		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
+		let indexer = indexer.clone();
+
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				indexer: indexer.clone(),
+				subscription_executor,
+				command_sink: command_sink.clone(),
+			};
 			crate::rpc::create_full(deps).map_err(Into::into)
 		})
 	};
@@ -245,6 +292,82 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		telemetry: telemetry.as_mut(),
 	})?;
 
+	if let Some(registry) = prometheus_registry.as_ref() {
+		let metrics = SocialGraphMetrics::register(registry)?;
+		task_manager.spawn_handle().spawn(
+			"social-graph-metrics",
+			None,
+			social_graph_metrics(client.clone(), metrics),
+		);
+	}
+
+	if let Some(indexer) = indexer {
+		task_manager.spawn_handle().spawn_blocking(
+			"social-graph-indexer",
+			None,
+			crate::indexer::run(client.clone(), indexer),
+		);
+	}
+
+	if let Some(sealing) = sealing {
+		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool.clone(),
+			prometheus_registry.as_ref(),
+			telemetry.as_ref().map(|x| x.handle()),
+		);
+
+		let pool_commands = match sealing {
+			Sealing::Manual => stream::empty().boxed(),
+			Sealing::Instant => transaction_pool
+				.import_notification_stream()
+				.map(|_| EngineCommand::SealNewBlock {
+					create_empty: false,
+					finalize: true,
+					parent_hash: None,
+					sender: None,
+				})
+				.boxed(),
+		};
+
+		// Each sealed block moves time forward by one Aura slot, so blocks
+		// carry the slots and timestamps the runtime expects however fast
+		// they are sealed.
+		let inherent_client = client.clone();
+		let manual_seal = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+			block_import,
+			env: proposer_factory,
+			client: client.clone(),
+			pool: transaction_pool,
+			commands_stream: stream::select(commands_stream, pool_commands),
+			select_chain,
+			consensus_data_provider: Some(Box::new(AuraConsensusDataProvider::new(client))),
+			create_inherent_data_providers: move |_, ()| {
+				let client = inherent_client.clone();
+				async move {
+					let timestamp = SlotTimestampProvider::new_aura(client)
+						.map_err(|e| format!("Failed to create timestamp provider: {}", e))?;
+					let slot =
+						sp_consensus_aura::inherents::InherentDataProvider::new(timestamp.slot());
+
+					Ok((timestamp, slot))
+				}
+			},
+		});
+
+		// the sealing task is considered essential, i.e. if it fails we
+		// take down the service with it.
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"manual-seal",
+			Some("block-authoring"),
+			manual_seal,
+		);
+
+		network_starter.start_network();
+		return Ok(task_manager)
+	}
+
 	if role.is_authority() {
 		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
@@ -342,3 +465,147 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	network_starter.start_network();
 	Ok(task_manager)
 }
+
+/// Number of blocks within which a ban counts towards the recent bans gauge.
+const BAN_WINDOW: BlockNumber = DAYS;
+
+/// Least time between two full reads of the social graph's state. A full read walks every account,
+/// ban and challenge record, so only the counted totals are read on every block.
+const METRICS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Prometheus gauges of the social graph's state.
+struct SocialGraphMetrics {
+	accounts: Gauge<U64>,
+	total_attestations: Gauge<U64>,
+	average_confidence: Gauge<F64>,
+	valid_accounts: Gauge<U64>,
+	active_challenges: Gauge<U64>,
+	pending_verdicts: Gauge<U64>,
+	banned_accounts: Gauge<U64>,
+	recent_bans: Gauge<U64>,
+	challenge_outcomes: GaugeVec<U64>,
+}
+
+impl SocialGraphMetrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			accounts: register(
+				Gauge::new("social_graph_accounts", "Number of registered accounts")?,
+				registry,
+			)?,
+			total_attestations: register(
+				Gauge::new("social_graph_attestations", "Number of attestations on the network")?,
+				registry,
+			)?,
+			average_confidence: register(
+				Gauge::new(
+					"social_graph_average_confidence",
+					"Average weighted confidence of an attestation",
+				)?,
+				registry,
+			)?,
+			valid_accounts: register(
+				Gauge::new(
+					"social_graph_valid_accounts",
+					"Number of accounts that pass the validity check",
+				)?,
+				registry,
+			)?,
+			active_challenges: register(
+				Gauge::new(
+					"social_graph_active_challenges",
+					"Number of challenges being voted on",
+				)?,
+				registry,
+			)?,
+			pending_verdicts: register(
+				Gauge::new(
+					"social_graph_pending_verdicts",
+					"Number of verdicts waiting for the council's veto period",
+				)?,
+				registry,
+			)?,
+			banned_accounts: register(
+				Gauge::new("social_graph_banned_accounts", "Number of banned accounts")?,
+				registry,
+			)?,
+			recent_bans: register(
+				Gauge::new(
+					"social_graph_recent_bans",
+					"Number of accounts banned in the last day",
+				)?,
+				registry,
+			)?,
+			challenge_outcomes: register(
+				GaugeVec::new(
+					Opts::new(
+						"social_graph_challenge_outcomes",
+						"Number of closed challenges by outcome",
+					),
+					&["outcome"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Updates the gauges backed by on-chain counters.
+	fn update_totals(&self, stats: &GraphStats) {
+		self.accounts.set(stats.accounts.into());
+		self.total_attestations.set(stats.total_attestations.into());
+		self.average_confidence.set(if stats.total_attestations == 0 {
+			0.0
+		} else {
+			f64::from(stats.total_confidence) / f64::from(stats.total_attestations)
+		});
+		self.active_challenges.set(stats.active_challenges.into());
+		self.pending_verdicts.set(stats.pending_verdicts.into());
+	}
+
+	/// Updates every gauge.
+	fn update(&self, stats: &GraphStats) {
+		self.update_totals(stats);
+		self.valid_accounts.set(stats.valid_accounts.into());
+		self.banned_accounts.set(stats.banned_accounts.into());
+		self.recent_bans.set(stats.recent_bans.into());
+		for (outcome, count) in [
+			("banned", stats.challenges_banned),
+			("cleared", stats.challenges_cleared),
+			("appealed", stats.challenges_appealed),
+			("cancelled", stats.challenges_cancelled),
+		] {
+			self.challenge_outcomes.with_label_values(&[outcome]).set(count.into());
+		}
+	}
+}
+
+/// Updates the gauges on every imported best block. The counted totals are read each time; valid
+/// accounts, bans and challenge outcomes need a walk over the graph and are refreshed at most once
+/// per `METRICS_INTERVAL`. Only new best blocks are read so the gauges don't jump between forks.
+async fn social_graph_metrics(client: Arc<FullClient>, metrics: SocialGraphMetrics) {
+	let mut imports = client.import_notification_stream();
+	let mut last_read: Option<Instant> = None;
+	while let Some(notification) = imports.next().await {
+		if !notification.is_new_best {
+			continue
+		}
+		let at = BlockId::Hash(notification.hash);
+		let full = last_read.map_or(true, |read| read.elapsed() >= METRICS_INTERVAL);
+		let stats = if full {
+			last_read = Some(Instant::now());
+			client.runtime_api().graph_stats(&at, BAN_WINDOW)
+		} else {
+			client.runtime_api().graph_totals(&at)
+		};
+		match stats {
+			Ok(stats) if full => metrics.update(&stats),
+			Ok(stats) => metrics.update_totals(&stats),
+			Err(e) => log::warn!(
+				target: "social-graph",
+				"Failed to read social graph stats at {}: {}",
+				notification.hash,
+				e
+			),
+		}
+	}
+}
@@ -0,0 +1,27 @@
+//! Runtime API of the social-graph pallet. The node uses it to read summaries
+//! of the graph's state without decoding storage itself.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+
+pub use social_graph::GraphStats;
+
+sp_api::decl_runtime_apis! {
+	/// Read-only queries of the social graph.
+	pub trait SocialGraphApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// Summary of the graph's state. Bans within `window` blocks of the
+		/// queried block count as recent.
+		fn graph_stats(window: BlockNumber) -> GraphStats;
+
+		/// The counted part of `graph_stats`, cheap enough for every block.
+		/// Fields that need a walk over the graph are zero.
+		fn graph_totals() -> GraphStats;
+
+		/// Whether `account` may currently attest, vote and challenge.
+		fn is_valid(account: AccountId) -> bool;
+	}
+}
@@ -5,6 +5,8 @@
 /// <https://docs.substrate.io/v3/runtime/frame>
 pub use pallet::*;
 
+pub mod migrations;
+
 #[cfg(test)]
 mod mock;
 
@@ -16,19 +18,32 @@ mod benchmarking;
 
 #[frame_support::pallet]
 pub mod pallet {
-	use frame_support::pallet_prelude::*;
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{BalanceStatus, ChangeMembers, Currency, ReservableCurrency},
+		CloneNoBound, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound,
+	};
 	use frame_system::pallet_prelude::*;
 	use sp_runtime::{
 		traits::{
-			StaticLookup
-		}
+			CheckedRem, Convert, IdentifyAccount, Saturating, StaticLookup, Verify, Zero
+		},
+		Percent,
 	};
+	#[cfg(feature = "std")]
+	use serde::{Deserialize, Serialize};
+	use sp_std::prelude::*;
+	#[cfg(any(feature = "try-runtime", test))]
+	use sp_std::collections::btree_set::BTreeSet;
 
 
 	/// Value an account attaches to their attestation representing their 
 	/// confidence in the target account's validity
 	type Confidence = u8;
 
+	/// Number of months an attester has known the target
+	type KnownFor = u16;
+
 	/// A count of attestations
 	type AttestCount = u32;
 
@@ -39,11 +54,308 @@ pub mod pallet {
 	/// Greater numbers indicate greater suspicion 
 	type Vote = i8;
 
+	/// Code an operator gives for a moderation action, for the audit trail
+	type ReasonCode = u16;
+
 	/// Total number of attestations on the network
 	type TotalAttestations = u32;
 	/// Sum of all confidence on the network
 	type SumAllConfidence = u32;
 
+	/// Unique identifier of a challenge
+	pub type ChallengeId = u32;
+
+	/// Balance type of the currency used for deposits
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	/// How an attester knows the account they attest for
+	#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub enum Relationship {
+		/// Attested before relationships were recorded
+		Unspecified,
+		/// A family member
+		Family,
+		/// Someone the attester works with
+		Colleague,
+		/// Someone the attester has met in person
+		MetInPerson,
+		/// Someone the attester only knows online
+		OnlineOnly,
+		/// A member of the same organisation
+		OrganisationMember,
+		/// Both parties met and vouched for each other with `handshake`. Only
+		/// the handshake can record this relationship.
+		InPersonHandshake,
+	}
+
+	/// An attestation for a target's personhood
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct Attestation<BlockNumber> {
+		/// Confidence in the target's validity: 0..10 (inclusive)
+		pub confidence: Confidence,
+		/// How the attester knows the target
+		pub relationship: Relationship,
+		/// How long the attester has known the target, in months
+		pub known_for: KnownFor,
+		/// Block the attestation was made
+		pub block: BlockNumber,
+	}
+
+	/// An attestation signed off-chain by the attester, to be submitted by any
+	/// account with `attest_signed`. The attester signs
+	/// `(ATTESTATION_CONTEXT, genesis_hash, payload)`, see `attestation_message`.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct AttestationPayload<AccountId, BlockNumber> {
+		/// Account making the attestation, whose key signs the payload
+		pub attester: AccountId,
+		/// Account being attested for
+		pub target: AccountId,
+		/// Confidence in the target's validity
+		pub confidence: Confidence,
+		/// How the attester knows the target
+		pub relationship: Relationship,
+		/// How long the attester has known the target, in months
+		pub known_for: KnownFor,
+		/// The attester's next nonce, so the payload can only be submitted once
+		pub nonce: u64,
+		/// Last block the payload can be submitted in
+		pub expiry: BlockNumber,
+	}
+
+	/// A claim two people sign when they meet in person, vouching for each
+	/// other. Either party submits it with both signatures via `handshake`.
+	/// Both sign `(HANDSHAKE_CONTEXT, genesis_hash, claim)`, see `handshake_message`.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct HandshakeClaim<AccountId, BlockNumber> {
+		/// One party of the handshake
+		pub first: AccountId,
+		/// The other party of the handshake
+		pub second: AccountId,
+		/// The first party's confidence in the second
+		pub first_confidence: Confidence,
+		/// The second party's confidence in the first
+		pub second_confidence: Confidence,
+		/// Nonce of the meeting, which neither party may have used before
+		pub session: u64,
+		/// Last block the claim can be submitted in
+		pub expiry: BlockNumber,
+	}
+
+	/// Context the attester signs along with the genesis hash and an
+	/// `AttestationPayload`, so the signature can't be replayed elsewhere
+	pub const ATTESTATION_CONTEXT: &[u8] = b"social-graph:attest";
+
+	/// Context both parties sign along with the genesis hash and a
+	/// `HandshakeClaim`, so the signatures can't be replayed elsewhere
+	pub const HANDSHAKE_CONTEXT: &[u8] = b"social-graph:handshake";
+
+	/// Context the new key signs along with both accounts to countersign a rotation
+	pub const ROTATION_CONTEXT: &[u8] = b"social-graph:rotate";
+
+	/// Highest confidence bound `set_parameters` accepts
+	const MAX_CONFIDENCE_LIMIT: Confidence = 100;
+
+	/// Highest mutual attestation requirement `set_parameters` accepts
+	const MIN_MUTUAL_ATTESTATIONS_LIMIT: u32 = 100;
+
+	/// Parameters of the social graph that governance can change on-chain
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub struct GraphParameters<BlockNumber, Balance> {
+		/// Number of blocks each challenge stays active
+		pub challenge_duration: BlockNumber,
+		/// Highest confidence an attestation may carry
+		pub max_confidence: Confidence,
+		/// Minimum number of mutual attestations an account needs to be valid
+		pub min_mutual_attestations: u32,
+		/// Maximum share of an account's attestations that may be mutual for
+		/// it to be valid. Rings of accounts vouching for each other are the
+		/// typical sybil pattern.
+		pub max_mutual_share: Percent,
+		/// Amount reserved from an account when it registers
+		pub registration_deposit: Balance,
+		/// Amount reserved from a challenger, returned if the suspect is banned
+		/// and slashed otherwise
+		pub challenge_bond: Balance,
+		/// Number of blocks the council has to overturn a verdict before it is
+		/// enacted. Zero enacts verdicts right away.
+		pub veto_period: BlockNumber,
+		/// Share of a lost account's weighted confidence its attesters must
+		/// confirm to recover it
+		pub recovery_threshold: Percent,
+		/// Number of blocks after the threshold is reached during which the
+		/// lost account can still cancel its recovery
+		pub recovery_delay: BlockNumber,
+		/// Share of the bond returned to a challenger who cancels their
+		/// challenge. The rest is slashed.
+		pub cancel_refund: Percent,
+		/// Number of votes below which a challenger may extend their challenge once
+		pub min_turnout: u32,
+	}
+
+	/// Recovery of a lost account to a new one, confirmed by its attesters
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct ActiveRecovery<AccountId, BlockNumber> {
+		/// New account that takes over the lost account's place in the graph
+		pub rescuer: AccountId,
+		/// Block the recovery started
+		pub started: BlockNumber,
+		/// Weighted confidence of the attesters who confirmed so far
+		pub confirmed: ConfidenceSum,
+		/// Weighted confidence of the attesters who rejected so far
+		pub rejected: ConfidenceSum,
+		/// Block the confirmations first reached the threshold, if they have
+		pub threshold_reached: Option<BlockNumber>,
+	}
+
+	/// Where a challenge is in its lifecycle
+	#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub enum ChallengeStatus {
+		/// Open, nobody has voted yet
+		Evidence,
+		/// Open with at least one vote
+		Voting,
+		/// The vote reached a verdict
+		Resolved,
+		/// The council overturned the verdict
+		Appealed,
+		/// Cancelled by the challenger or an admin without a verdict
+		Cancelled,
+	}
+
+	/// The result of a resolved challenge
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct ChallengeOutcome<BlockNumber> {
+		/// Sum of the votes
+		pub tally: i32,
+		/// Number of votes cast
+		pub turnout: u32,
+		/// Whether the verdict was to ban the suspect
+		pub banned: bool,
+		/// Block the verdict was reached
+		pub resolved: BlockNumber,
+		/// Block the verdict took effect, after any veto period
+		pub enacted: Option<BlockNumber>,
+	}
+
+	/// A challenge, kept after it closes as part of the suspect's and the
+	/// challenger's history
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct ChallengeRecord<AccountId, BlockNumber> {
+		/// Account being challenged
+		pub suspect: AccountId,
+		/// Account that opened the challenge
+		pub challenger: AccountId,
+		/// Where the challenge is in its lifecycle
+		pub status: ChallengeStatus,
+		/// Block the challenge was opened
+		pub opened: BlockNumber,
+		/// Block voting ends
+		pub final_block: BlockNumber,
+		/// The result, once resolved
+		pub outcome: Option<ChallengeOutcome<BlockNumber>>,
+	}
+
+	/// Summary of the graph's state, read by the node through the runtime API
+	/// to export metrics
+	#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub struct GraphStats {
+		/// Number of registered accounts
+		pub accounts: u32,
+		/// Number of attestations on the network
+		pub total_attestations: u32,
+		/// Sum of all weighted confidence on the network
+		pub total_confidence: u32,
+		/// Number of accounts that currently pass the validity check
+		pub valid_accounts: u32,
+		/// Number of challenges still being voted on
+		pub active_challenges: u32,
+		/// Number of verdicts waiting for the council's veto period
+		pub pending_verdicts: u32,
+		/// Number of banned accounts
+		pub banned_accounts: u32,
+		/// Number of accounts banned within the requested window
+		pub recent_bans: u32,
+		/// Number of closed challenges that banned the suspect
+		pub challenges_banned: u32,
+		/// Number of closed challenges that cleared the suspect
+		pub challenges_cleared: u32,
+		/// Number of challenges whose verdict the council overturned
+		pub challenges_appealed: u32,
+		/// Number of challenges cancelled without a verdict
+		pub challenges_cancelled: u32,
+	}
+
+	/// A challenge verdict waiting for the council's veto period to pass
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct PendingVerdict<AccountId, Balance, BlockNumber> {
+		/// Account that opened the challenge
+		pub challenger: AccountId,
+		/// The challenger's bond
+		pub bond: Balance,
+		/// Whether the vote decided to ban the suspect
+		pub ban: bool,
+		/// Block the verdict is enacted unless the council overturns it
+		pub finalize_at: BlockNumber,
+	}
+
+	/// A bounded field of a profile (display name, link, public key)
+	pub type ProfileField<T> = BoundedVec<u8, <T as Config>::MaxProfileFieldLength>;
+
+	/// Public metadata a member attaches to their account so attesters see
+	/// more than a raw account ID.
+	#[derive(Encode, Decode, CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct Profile<T: Config> {
+		/// Human readable name
+		pub display_name: ProfileField<T>,
+		/// Hash of the avatar image, stored off-chain
+		pub avatar: Option<T::Hash>,
+		/// A link, e.g. a personal website or social account
+		pub link: ProfileField<T>,
+		/// A public key used to verify the account's owner out-of-band
+		pub public_key: ProfileField<T>,
+		/// Amount reserved for this profile
+		pub deposit: BalanceOf<T>,
+		/// Block an existing profile was last changed, if ever. Attestations
+		/// older than this vouched for a previous identity.
+		pub changed: Option<T::BlockNumber>,
+	}
+
+
+	/// A signer of a notarized document. Only valid persons can sign, and
+	/// their standing when signing is kept with the signature.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub struct DocumentSigner<AccountId, BlockNumber> {
+		/// The signing account
+		pub account: AccountId,
+		/// Block the document was signed
+		pub block: BlockNumber,
+		/// Number of attestations the signer had when signing
+		pub attest_count: AttestCount,
+		/// Weighted confidence sum the signer had when signing
+		pub confidence: ConfidenceSum,
+	}
+
+	/// A document notarized by registered persons, identified by its hash.
+	#[derive(Encode, Decode, CloneNoBound, PartialEqNoBound, EqNoBound, RuntimeDebugNoBound, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct NotaryRecord<T: Config> {
+		/// Optional metadata, e.g. a title or a content type
+		pub metadata: Option<BoundedVec<u8, T::MaxDocumentMetadataLength>>,
+		/// Everyone who signed the document, the submitter first
+		pub signers: BoundedVec<DocumentSigner<T::AccountId, T::BlockNumber>, T::MaxDocumentSigners>,
+		/// Block a signer was first banned after signing, if any
+		pub signer_banned: Option<T::BlockNumber>,
+	}
+
+
+	/// The in-code storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(6);
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
@@ -53,33 +365,141 @@ pub mod pallet {
 		// consider adding MAX confidence value to change in runtime w votes
 		
 		#[pallet::constant]
-		/// Number of blocks the each challenge stays active
-		type ChallengeDuration: Get<u32>;
-
-		// Mayber add minimum stake for challenge and vote 
+		/// Longest challenge duration `set_parameters` accepts
+		type MaxChallengeDuration: Get<u32>;
 
 		#[pallet::constant]
-		/// Maximum number of challenges that can be active at once
+		/// Maximum number of challenges that can be active or awaiting their
+		/// verdict at once
 		type MaxChallenges: Get<u32>;
 
+		#[pallet::constant]
+		/// Maximum number of accounts that can vote on a single challenge
+		type MaxVotersPerChallenge: Get<u32>;
+
+		#[pallet::constant]
+		/// Maximum number of attestations in one `attest_many` batch
+		type MaxAttestBatch: Get<u32>;
+
+		/// The currency in which deposits and bonds are reserved.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Signature attesters use to sign attestations off-chain.
+		type OffchainSignature: Verify<Signer = Self::OffchainPublic> + Parameter;
+
+		/// Public key the off-chain signatures are checked against.
+		type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
+
+		/// Origin allowed to change the graph's parameters.
+		type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The moderation council, whose members are elected from the graph.
+		type Council: ChangeMembers<Self::AccountId>;
+
+		/// Origin of the moderation council's motions, allowed to overturn verdicts.
+		type CouncilOrigin: EnsureOrigin<Self::Origin>;
+
+		#[pallet::constant]
+		/// Number of members elected to the moderation council
+		type CouncilSize: Get<u32>;
+
+		#[pallet::constant]
+		/// Number of blocks between council elections. Zero disables elections.
+		type CouncilTerm: Get<Self::BlockNumber>;
+
+		#[pallet::constant]
+		/// Number of accounts with the highest standing kept as council
+		/// candidates, so an election only iterates the whole graph when bans
+		/// or departures left fewer candidates than that
+		type MaxCouncilCandidates: Get<u32>;
+
+		#[pallet::constant]
+		/// Maximum length in bytes of each profile field
+		type MaxProfileFieldLength: Get<u32>;
+
+		#[pallet::constant]
+		/// Base amount reserved for setting a profile
+		type ProfileDepositBase: Get<BalanceOf<Self>>;
+
+		#[pallet::constant]
+		/// Amount reserved per byte of profile data
+		type ProfileByteDeposit: Get<BalanceOf<Self>>;
+
+		/// Multiplier applied to an attestation's confidence depending on the
+		/// relationship, before it counts towards AccountData and TotalsCounter.
+		/// Changing it requires recomputing both (see `migrations`).
+		type RelationshipWeight: Convert<Relationship, u32>;
+
+		#[pallet::constant]
+		/// Maximum number of account rotations waiting to be processed
+		type MaxPendingRotations: Get<u32>;
+
+		#[pallet::constant]
+		/// Number of attestations or accounts a rotation processes per block
+		type RotationStepsPerBlock: Get<u32>;
+
+		#[pallet::constant]
+		/// Maximum length of a notarized document's metadata
+		type MaxDocumentMetadataLength: Get<u32>;
+
+		#[pallet::constant]
+		/// Maximum number of signers of a notarized document
+		type MaxDocumentSigners: Get<u32>;
+
+		#[pallet::constant]
+		/// Maximum number of documents a single account can sign
+		type MaxSignedDocuments: Get<u32>;
+
+		#[pallet::constant]
+		/// Number of blocks a recovery has to reach its threshold before it
+		/// expires and another rescuer can start over
+		type RecoveryPeriod: Get<Self::BlockNumber>;
+
 	}
 
 
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 
+	#[pallet::type_value]
+	/// Parameters used until governance or genesis sets them.
+	pub fn DefaultParameters<T: Config>() -> GraphParameters<T::BlockNumber, BalanceOf<T>> {
+		GraphParameters {
+			challenge_duration: 8u32.into(),
+			max_confidence: 10,
+			min_mutual_attestations: 0,
+			max_mutual_share: Percent::from_percent(100),
+			registration_deposit: Zero::zero(),
+			challenge_bond: Zero::zero(),
+			veto_period: Zero::zero(),
+			recovery_threshold: Percent::from_percent(50),
+			recovery_delay: 100u32.into(),
+			cancel_refund: Percent::from_percent(50),
+			min_turnout: 3,
+		}
+	}
+
+	#[pallet::storage]
+	#[pallet::getter(fn parameters)]
+	/// The graph's tunable parameters.
+	pub type Parameters<T: Config> = StorageValue<_,
+		GraphParameters<T::BlockNumber, BalanceOf<T>>, ValueQuery, DefaultParameters<T>>;
+
+
 	// Attestation storage types:
 	#[pallet::storage]
 	#[pallet::getter(fn attestations)]
-	/// Attestations is a double storage map holding
+	/// Attestations is a double storage map holding the target as key1, the
+	/// attester as key2, and the attestation
 	pub type Attestations<T: Config> = StorageDoubleMap<_, Blake2_128Concat, 
-		T::AccountId, Blake2_128Concat, T::AccountId, (Confidence, T::BlockNumber)>;
+		T::AccountId, Blake2_128Concat, T::AccountId, Attestation<T::BlockNumber>>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn account_data)]
-	/// All accounts' data (# attestations, sum of confidence, birth block).
+	/// All accounts' data (# attestations, sum of weighted confidence, birth block).
 	pub type AccountData<T: Config> = CountedStorageMap<_, Blake2_128Concat, 
 		T::AccountId, (AttestCount, ConfidenceSum, T::BlockNumber)>;
 
@@ -89,6 +509,23 @@ pub mod pallet {
 	pub type TotalsCounter<T: Config> = StorageValue<_, 
 		(TotalAttestations, SumAllConfidence)>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn mutual_count)]
+	/// Number of accounts each account has a mutual attestation with, i.e.
+	/// both attested for each other.
+	pub type MutualCount<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn registration_deposits)]
+	/// The deposit each registered account has reserved, returned when it leaves.
+	pub type RegistrationDeposits<T: Config> = StorageMap<_, Blake2_128Concat,
+		T::AccountId, BalanceOf<T>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn profiles)]
+	/// Profile metadata of graph members.
+	pub type Profiles<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, Profile<T>>;
+
 
 	// Challenge Storage types:
 	#[pallet::storage]
@@ -105,6 +542,32 @@ pub mod pallet {
 	/// value: Challenger
 	pub type ActiveChallenges<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn next_challenge_id)]
+	/// ID the next challenge gets.
+	pub type NextChallengeId<T: Config> = StorageValue<_, ChallengeId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn challenge_records)]
+	/// Every challenge ever opened, by ID.
+	pub type ChallengeRecords<T: Config> = StorageMap<_, Twox64Concat, ChallengeId,
+		ChallengeRecord<T::AccountId, T::BlockNumber>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn open_challenges)]
+	/// ID of each suspect's challenge that is active or awaiting its verdict.
+	pub type OpenChallenges<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ChallengeId>;
+
+	#[pallet::storage]
+	/// Index of challenges by suspect.
+	pub type ChallengesBySuspect<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId,
+		Twox64Concat, ChallengeId, ()>;
+
+	#[pallet::storage]
+	/// Index of challenges by challenger.
+	pub type ChallengesByChallenger<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId,
+		Twox64Concat, ChallengeId, ()>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn votes)]
 	/// A double storage map containing the suspect's ID as key1, the voter
@@ -112,6 +575,119 @@ pub mod pallet {
 	pub type Votes<T: Config> = StorageDoubleMap<_, Blake2_128Concat, 
 		T::AccountId, Blake2_128Concat, T::AccountId, Vote>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn tallies)]
+	/// Running sum and count of the votes on each active challenge, keyed by
+	/// suspect. Updated as votes come in so resolving a challenge is O(1).
+	pub type Tallies<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, (i32, u32), ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn stale_votes)]
+	/// Suspects of closed challenges whose votes are still stored. They are
+	/// deleted in `on_idle` or by `clear_stale_votes`; until then the suspect
+	/// can't be challenged again.
+	pub type StaleVotes<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn challenge_bonds)]
+	/// The bond each challenger reserved, keyed by suspect.
+	pub type ChallengeBonds<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn attest_nonces)]
+	/// Nonce the next off-chain signed attestation of each attester must carry.
+	pub type AttestNonces<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u64, ValueQuery>;
+
+	#[pallet::storage]
+	/// Handshake sessions each account has taken part in, so claims can't be replayed.
+	pub type HandshakeSessions<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId,
+		Twox64Concat, u64, (), OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn documents)]
+	/// Notarized documents by hash, with their full list of signers.
+	pub type Documents<T: Config> = StorageMap<_, Identity, T::Hash, NotaryRecord<T>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn signed_documents)]
+	/// Hashes of the documents each account signed, to mark them if it is banned.
+	pub type SignedDocuments<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId,
+		BoundedVec<T::Hash, T::MaxSignedDocuments>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn recoveries)]
+	/// Ongoing recoveries, keyed by the lost account.
+	pub type Recoveries<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId,
+		ActiveRecovery<T::AccountId, T::BlockNumber>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn recovery_confirmations)]
+	/// Attesters who confirmed a recovery and the weighted confidence they
+	/// count with. The first key is the lost account, the second the attester.
+	pub type RecoveryConfirmations<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId,
+		Blake2_128Concat, T::AccountId, ConfidenceSum>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn recovery_rejections)]
+	/// Attesters who rejected a recovery and the weighted confidence they
+	/// count with. The first key is the lost account, the second the attester.
+	pub type RecoveryRejections<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId,
+		Blake2_128Concat, T::AccountId, ConfidenceSum>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn retired_accounts)]
+	/// Accounts replaced by a recovery, pointing to the account that replaced them.
+	pub type RetiredAccounts<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn rotation_queue)]
+	/// Rotations whose attestations are still being re-keyed, as (old, new)
+	/// tuples. The first one is processed at the start of each block.
+	pub type RotationQueue<T: Config> = StorageValue<_,
+		BoundedVec<(T::AccountId, T::AccountId), T::MaxPendingRotations>, ValueQuery>;
+
+	#[pallet::storage]
+	/// Last account scanned for attestations given by the rotation in progress.
+	pub type RotationCursor<T: Config> = StorageValue<_, T::AccountId>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn rotating_accounts)]
+	/// New accounts of rotations still in progress, pointing to the old account.
+	pub type RotatingAccounts<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn pending_verdicts)]
+	/// Verdicts of finished challenges the council can still overturn, keyed by suspect.
+	pub type PendingVerdicts<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId,
+		PendingVerdict<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn verdict_queue)]
+	/// A bounded vec of all pending verdicts. Each is represented by a tuple
+	/// containing the suspect's account ID and the block it is enacted.
+	/// Challenges and pending verdicts share `MaxChallenges`, so a finished
+	/// challenge always finds room here.
+	pub type VerdictQueue<T: Config> = StorageValue<_,
+		BoundedVec<(T::AccountId, T::BlockNumber), T::MaxChallenges>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn council_members)]
+	/// Members of the moderation council, sorted.
+	pub type CouncilMembers<T: Config> = StorageValue<_,
+		BoundedVec<T::AccountId, T::CouncilSize>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn council_candidates)]
+	/// The registered, unbanned accounts with the highest standing, highest
+	/// first. (Account, ConfidenceSum, AttestCount)
+	pub type CouncilCandidates<T: Config> = StorageValue<_,
+		BoundedVec<(T::AccountId, ConfidenceSum, AttestCount), T::MaxCouncilCandidates>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn extended_challenges)]
+	/// Active challenges the challenger already extended, keyed by suspect.
+	pub type ExtendedChallenges<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn banned_accounts)]
 	/// Stores all banned accounts and with the block they were banned 
@@ -120,18 +696,169 @@ pub mod pallet {
 
 
 
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		/// Initial parameters of the graph.
+		pub parameters: GraphParameters<T::BlockNumber, BalanceOf<T>>,
+		/// Attestations the graph starts with (attester, target, confidence).
+		/// Both parties are registered without a deposit.
+		pub attestations: Vec<(T::AccountId, T::AccountId, Confidence)>,
+		/// Accounts banned from the start.
+		pub banned: Vec<T::AccountId>,
+		/// Challenges open from the start (challenger, suspect), without a bond.
+		pub challenges: Vec<(T::AccountId, T::AccountId)>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self {
+				parameters: DefaultParameters::<T>::get(),
+				attestations: Vec::new(),
+				banned: Vec::new(),
+				challenges: Vec::new(),
+			}
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			<Parameters<T>>::put(self.parameters.clone());
+
+			// Register everyone in the seeded graph.
+			for (attester, target, _) in &self.attestations {
+				for account in [attester, target] {
+					if !<AccountData<T>>::contains_key(account) {
+						<AccountData<T>>::insert(account, (0, 0, T::BlockNumber::zero()));
+					}
+				}
+			}
+
+			// Attestations are applied like regular ones, so the counts and
+			// mutual pairs line up.
+			let mut totals = (0, 0);
+			for (attester, target, confidence) in &self.attestations {
+				assert!(attester != target, "Genesis attestation is a self-attestation");
+				assert!(*confidence <= self.parameters.max_confidence, "Genesis confidence out of bounds");
+				let attestation = Attestation {
+					confidence: *confidence,
+					relationship: Relationship::Unspecified,
+					known_for: 0,
+					block: T::BlockNumber::zero(),
+				};
+				Pallet::<T>::apply_attestation(attester.clone(), target.clone(), attestation, &mut totals);
+			}
+			if !self.attestations.is_empty() {
+				<TotalsCounter<T>>::put(totals);
+			}
+
+			for account in &self.banned {
+				<BannedAccounts<T>>::insert(account, T::BlockNumber::zero());
+			}
+			for account in <AccountData<T>>::iter_keys() {
+				Pallet::<T>::update_candidate(account);
+			}
+
+			for (challenger, suspect) in &self.challenges {
+				assert!(<AccountData<T>>::contains_key(challenger), "Genesis challenger is not registered");
+				assert!(<AccountData<T>>::contains_key(suspect), "Genesis suspect is not registered");
+				assert!(!<ActiveChallenges<T>>::contains_key(suspect), "Genesis suspect is challenged twice");
+				Pallet::<T>::open_challenge(challenger.clone(), suspect.clone(), Zero::zero())
+					.expect("Genesis challenges fit in MaxChallenges");
+			}
+		}
+	}
+
+
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/v3/runtime/events-and-errors
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
-		/// Event documentation should end with an array that provides descriptive names for event
-		/// parameters. [something, who]
-		Attested(T::AccountId, T::AccountId, (Confidence, T::BlockNumber)),
-		/// A challence was issued! (Challenger, Suspect, FinalBlock)
-		ChallengeCreated(T::AccountId, T::AccountId, T::BlockNumber),
-		/// A vote has been submitted to an active challenge.
-		VoteSubmitted(T::AccountId, T::AccountId),
+		/// An attestation was made, or moved to the key that recovered or
+		/// rotated one of its accounts. (Attester, Target, Attestation)
+		Attested(T::AccountId, T::AccountId, Attestation<T::BlockNumber>),
+		/// A challence was issued! (ChallengeId, Challenger, Suspect, FinalBlock)
+		ChallengeCreated(ChallengeId, T::AccountId, T::AccountId, T::BlockNumber),
+		/// A challenge reached a verdict. (ChallengeId, Suspect, Outcome)
+		ChallengeResolved(ChallengeId, T::AccountId, ChallengeOutcome<T::BlockNumber>),
+		/// A vote has been submitted to an active challenge. (Voter, Suspect, Vote)
+		VoteSubmitted(T::AccountId, T::AccountId, Vote),
+		/// An account joined the social graph. (Account, Deposit)
+		Registered(T::AccountId, BalanceOf<T>),
+		/// An account left the social graph and its deposit was returned. (Account, Deposit)
+		Left(T::AccountId, BalanceOf<T>),
+		/// An account set or changed its profile. (Account, Deposit)
+		ProfileSet(T::AccountId, BalanceOf<T>),
+		/// An account changed an existing profile. Attestations it received
+		/// before this block vouched for its previous identity. (Account, Block)
+		ProfileChanged(T::AccountId, T::BlockNumber),
+		/// An account removed its profile and its deposit was returned. (Account, Deposit)
+		ProfileCleared(T::AccountId, BalanceOf<T>),
+		/// Two accounts have attested for each other. (Account, Account)
+		MutualAttestation(T::AccountId, T::AccountId),
+		/// Two accounts no longer attest for each other. (Account, Account)
+		MutualAttestationRemoved(T::AccountId, T::AccountId),
+		/// The graph's parameters were changed.
+		ParametersSet(GraphParameters<T::BlockNumber, BalanceOf<T>>),
+		/// An admin banned an account. (Account, Reason)
+		ForceBanned(T::AccountId, ReasonCode),
+		/// An admin lifted an account's ban. (Account, Reason)
+		ForceUnbanned(T::AccountId, ReasonCode),
+		/// An admin resolved a challenge early. (Suspect, Banned)
+		ChallengeForceResolved(T::AccountId, bool),
+		/// An admin cancelled a challenge and returned its bond. (Suspect)
+		ChallengeForceCancelled(T::AccountId),
+		/// An admin removed an attestation. (Target, Attester)
+		AttestationForceRemoved(T::AccountId, T::AccountId),
+		/// An attestation went away because its target left or was recovered,
+		/// or either account moved to a new key. (Target, Attester)
+		AttestationRemoved(T::AccountId, T::AccountId),
+		/// A challenge reached a verdict the council can overturn until the
+		/// final block. (Suspect, Ban, FinalBlock)
+		VerdictPending(T::AccountId, bool, T::BlockNumber),
+		/// A verdict was enacted. (Suspect, Banned)
+		VerdictEnacted(T::AccountId, bool),
+		/// The council overturned a verdict. (Suspect, Banned)
+		VerdictOverturned(T::AccountId, bool),
+		/// Two accounts completed an in-person handshake. (First, Second, Session)
+		HandshakeCompleted(T::AccountId, T::AccountId, u64),
+		/// A document was notarized. (Hash, Submitter)
+		DocumentNotarized(T::Hash, T::AccountId),
+		/// A document was co-signed. (Hash, Signer)
+		DocumentSigned(T::Hash, T::AccountId),
+		/// A signer withdrew its signature from a document. (Hash, Signer)
+		DocumentUnsigned(T::Hash, T::AccountId),
+		/// The submitter withdrew, removing the document. (Hash, Submitter)
+		DocumentRemoved(T::Hash, T::AccountId),
+		/// A signer of a document was banned. (Hash, Signer)
+		DocumentSignerBanned(T::Hash, T::AccountId),
+		/// A banned signer of a document was unbanned. (Hash, Signer)
+		DocumentSignerUnbanned(T::Hash, T::AccountId),
+		/// Recovery of a lost account started. (Lost, Rescuer)
+		RecoveryInitiated(T::AccountId, T::AccountId),
+		/// An attester confirmed a recovery. (Lost, Attester, Weight)
+		RecoveryConfirmed(T::AccountId, T::AccountId, ConfidenceSum),
+		/// An attester rejected a recovery. (Lost, Attester, Weight)
+		RecoveryRejected(T::AccountId, T::AccountId, ConfidenceSum),
+		/// A recovery was cancelled by the lost account or its attesters, or
+		/// replaced after it expired. (Lost)
+		RecoveryCancelled(T::AccountId),
+		/// A lost account was recovered and retired. (Lost, Rescuer)
+		AccountRecovered(T::AccountId, T::AccountId),
+		/// An account started moving its identity to a new key. (Old, New)
+		RotationStarted(T::AccountId, T::AccountId),
+		/// All of an account's attestations were moved to its new key. (Old, New)
+		AccountRotated(T::AccountId, T::AccountId),
+		/// A voter withdrew their vote. (Voter, Suspect)
+		VoteWithdrawn(T::AccountId, T::AccountId),
+		/// The challenger cancelled their challenge. (Suspect, Refund)
+		ChallengeCancelled(T::AccountId, BalanceOf<T>),
+		/// The challenger extended their challenge. (Suspect, NewFinalBlock)
+		ChallengeExtended(T::AccountId, T::BlockNumber),
+		/// A new moderation council was elected. (Members)
+		CouncilElected(Vec<T::AccountId>),
 	}
 
 
@@ -162,14 +889,134 @@ pub mod pallet {
 		ChallengeNotFound,
 		/// This challenge is already active. Go vote!
 		ChallengeAlreadyExists,
+		/// The account is already registered in the social graph.
+		AlreadyRegistered,
+		/// The account is not registered in the social graph.
+		NotRegistered,
+		/// Only registered accounts can be attested for.
+		UnregisteredTarget,
+		/// Banned accounts may not register.
+		AccountBanned,
+		/// An account may not leave while it is being challenged.
+		UnderChallenge,
+		/// The account has no profile.
+		ProfileNotFound,
+		/// The parameters are outside of sane bounds.
+		InvalidParameters,
+		/// The account is already banned.
+		AlreadyBanned,
+		/// The account is not banned.
+		NotBanned,
+		/// Cannot find the attestation
+		AttestationNotFound,
+		/// The suspect's last challenge has a verdict waiting for the council.
+		VerdictPending,
+		/// Cannot find a pending verdict for the suspect.
+		VerdictNotFound,
+		/// The same target appears twice in a batch.
+		DuplicateTarget,
+		/// The signature does not match the attester and payload.
+		InvalidSignature,
+		/// The payload's nonce is not the attester's next nonce.
+		InvalidNonce,
+		/// The payload's expiry block has passed.
+		PayloadExpired,
+		/// The relationship can only be recorded by a handshake.
+		ReservedRelationship,
+		/// Only a party of the handshake can submit it.
+		NotHandshakeParty,
+		/// A party already used the handshake's session nonce.
+		SessionAlreadyUsed,
+		/// Only valid persons can notarize or sign documents.
+		InvalidSigner,
+		/// The document has already been notarized.
+		AlreadyNotarized,
+		/// Cannot find the notarized document.
+		DocumentNotFound,
+		/// The account already signed the document.
+		AlreadySigned,
+		/// The document has reached `MaxDocumentSigners`.
+		TooManySigners,
+		/// The account has reached `MaxSignedDocuments`.
+		TooManySignedDocuments,
+		/// The account did not sign the document.
+		NotSigned,
+		/// The account was replaced by a recovery.
+		AccountRetired,
+		/// The lost account is already being recovered.
+		RecoveryInProgress,
+		/// Cannot find a recovery for the account.
+		RecoveryNotFound,
+		/// Only attesters of the lost account can confirm its recovery.
+		NotAGuardian,
+		/// The attester already confirmed or rejected the recovery.
+		AlreadyConfirmed,
+		/// The recovery did not reach its threshold within `RecoveryPeriod`.
+		RecoveryExpired,
+		/// Not enough attesters confirmed the recovery yet.
+		ThresholdNotReached,
+		/// The recovery delay has not passed yet.
+		RecoveryDelayPending,
+		/// Only the rescuer can claim the recovery.
+		NotRescuer,
+		/// The account is being rotated to a new key.
+		RotationInProgress,
+		/// Too many rotations are waiting to be processed.
+		TooManyRotations,
+		/// An account cannot be rotated to itself.
+		InvalidRotation,
+		/// Cannot find the origin's vote on the challenge.
+		VoteNotFound,
+		/// Every challenge ID has been used.
+		ChallengeIdOverflow,
+		/// The votes of the suspect's previous challenge have not been cleared yet.
+		StaleVotesPending,
+		/// Someone other than the challenger and suspect already voted.
+		ChallengeHasVotes,
+		/// The challenge was already extended once.
+		AlreadyExtended,
+		/// Enough votes were cast, the challenge can't be extended.
+		TurnoutReached,
+		/// The challenge has reached `MaxVotersPerChallenge`.
+		TooManyVoters,
+		/// The suspect has no stored votes left to clear.
+		NoStaleVotes,
+		/// The witness is lower than the number of items the call would process.
+		WitnessTooLow,
+		/// The new account still votes on an active challenge. Withdraw the votes first.
+		NewAccountVoted,
 	}
 
 
 	#[pallet::hooks]
 	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_runtime_upgrade() -> Weight {
+			crate::migrations::v1::migrate::<T>()
+				.saturating_add(crate::migrations::v2::migrate::<T>())
+				.saturating_add(crate::migrations::v3::migrate::<T>())
+				.saturating_add(crate::migrations::v4::migrate::<T>())
+				.saturating_add(crate::migrations::v5::migrate::<T>())
+				.saturating_add(crate::migrations::v6::migrate::<T>())
+		}
+
 		//Weight: see `begin_block`
 		fn on_initialize(n: T::BlockNumber) -> Weight {
-			Self::begin_block(n)
+			let mut weight = Self::begin_block(n);
+			weight = weight.saturating_add(Self::process_rotation());
+			if n.checked_rem(&T::CouncilTerm::get()).map_or(false, |rem| rem.is_zero()) {
+				weight = weight.saturating_add(Self::elect_council());
+			}
+			weight
+		}
+
+		// Spare block weight goes to deleting the votes of closed challenges.
+		fn on_idle(_n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			Self::clear_stale_votes_within(remaining_weight)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			Self::do_try_state()
 		}
 	}
 
@@ -182,97 +1029,177 @@ pub mod pallet {
 	impl<T: Config> Pallet<T> {
 		/// Takes in an origin account and a target account along with an 
 		/// attestation. The origin attests for the target's personhood with a 
-		/// confidence value 0..10 (inclusive). The confidence along with the 
-		/// current block number are written to a double map `attestations`
-		/// where the first key is the target being attested for and the second
-		/// is the origin who is sending their attestation. The origin cannot
-		/// attest for themselves.
-		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		/// confidence value 0..max_confidence (inclusive, 10 by default), says how it knows the target and
+		/// for how many months. The attestation along with the current block
+		/// number are written to a double map `attestations` where the first
+		/// key is the target being attested for and the second is the origin
+		/// who is sending their attestation. The origin cannot attest for
+		/// themselves. The confidence counts towards the target's AccountData
+		/// weighted by `RelationshipWeight`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(20, 6))]
 		pub fn attest(
 			origin: OriginFor<T>,
 			target: <T::Lookup as StaticLookup>::Source,
 			confidence: Confidence,
+			relationship: Relationship,
+			known_for: KnownFor,
 		) -> DispatchResult {
 
-			// Ensure that confidence is within the valid range 0..10 (inclusive).
-			ensure!(confidence <= 10, Error::<T>::ConfidenceOutOfBounds);
-
 			// Check origin is signed and lookup the target.
 			let origin = ensure_signed(origin)?;
 			let dest = T::Lookup::lookup(target)?;
 
-			// Ensure that origin and dest are not the same account.
-			ensure!(origin.clone() != dest.clone(), Error::<T>::SelfAttestationError);
-			
-			// Retrieve the current block number for later reference
-			let current_block = <frame_system::Pallet<T>>::block_number();
+			Self::do_attest(origin, dest, confidence, relationship, known_for)
+		}
 
-			// Get latest attest count, initialize if empty
-			let (total_attest_count, sum_confidence) = match <TotalsCounter<T>>::try_get() {
-				Ok(tup) => tup,
-				Err(_) => {
-					<TotalsCounter<T>>::put((0, 0));
-					(0, 0)
-				}
-			};
 
-			// Ensure attester is valid
-			ensure!(Self::check_account_validity(origin.clone()), Error::<T>::InvalidAttester);
-			
+		/// Submit an attestation the attester signed off-chain, so attesters
+		/// need no funds of their own. Any account can relay the payload and
+		/// pays the fee. The signature over `attestation_message` is checked
+		/// against the attester's key, the payload must carry the attester's next nonce and be submitted
+		/// by its expiry block. Otherwise it is treated exactly like `attest`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(21, 7))]
+		pub fn attest_signed(
+			origin: OriginFor<T>,
+			payload: AttestationPayload<T::AccountId, T::BlockNumber>,
+			signature: T::OffchainSignature,
+		) -> DispatchResult {
 
-			// Update storage (Attestations and Account Data).
-			
-			if <Attestations<T>>::contains_key(dest.clone(), origin.clone()) { // if attestations contains the key pair already it means we're chanching values of an existing attestation
-				
-			// Calculate the difference between new and old confidence values to update AccountData
-				let confidence_diff = confidence - <Attestations<T>>::get(dest.clone(), origin.clone()).unwrap().0; 
+			// Any signed account may relay the payload.
+			ensure_signed(origin)?;
 
-				// Deconstruct latest AccountData for later reference
-				let (og_count, og_confidence, birth_block) = <AccountData<T>>::get(dest.clone()).unwrap();
+			// Check the payload is still fresh and signed by the attester.
+			ensure!(payload.expiry >= <frame_system::Pallet<T>>::block_number(), Error::<T>::PayloadExpired);
+			let nonce = <AttestNonces<T>>::get(payload.attester.clone());
+			ensure!(payload.nonce == nonce, Error::<T>::InvalidNonce);
+			ensure!(signature.verify(&Self::attestation_message(&payload)[..], &payload.attester),
+				Error::<T>::InvalidSignature);
 
-				// Update account data.
-				<AccountData<T>>::insert(dest.clone(), (
-					og_count, // do not increment because key pair already exists  
-					og_confidence + u32::from(confidence_diff), // add the diff
-					birth_block)); // leave birth block unchainged 
+			let AttestationPayload { attester, target, confidence, relationship, known_for, .. } = payload;
+			Self::do_attest(attester.clone(), target, confidence, relationship, known_for)?;
 
-			} else { // if the key pair doesn't exist yet, this is a new attestation
+			// Use up the nonce so the payload can't be replayed.
+			<AttestNonces<T>>::insert(attester, nonce.saturating_add(1));
+			Ok(())
+		}
+
+
+		/// Complete an in-person handshake: two people who met sign the same
+		/// claim's `handshake_message`, and either of them submits it with both signatures. Both
+		/// attestations are written at once and marked with the
+		/// `InPersonHandshake` relationship. The claim is rejected if either
+		/// signature is wrong, it expired, or either party already used its
+		/// session nonce. Both parties must be valid attesters.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(8, 8))]
+		pub fn handshake(
+			origin: OriginFor<T>,
+			claim: HandshakeClaim<T::AccountId, T::BlockNumber>,
+			first_signature: T::OffchainSignature,
+			second_signature: T::OffchainSignature,
+		) -> DispatchResult {
+
+			let origin = ensure_signed(origin)?;
+			ensure!(origin == claim.first || origin == claim.second, Error::<T>::NotHandshakeParty);
+			ensure!(claim.first != claim.second, Error::<T>::SelfAttestationError);
+
+			// Check the claim is fresh and signed by both parties.
+			let current_block = <frame_system::Pallet<T>>::block_number();
+			ensure!(claim.expiry >= current_block, Error::<T>::PayloadExpired);
+			ensure!(!<HandshakeSessions<T>>::contains_key(claim.first.clone(), claim.session) &&
+				!<HandshakeSessions<T>>::contains_key(claim.second.clone(), claim.session),
+				Error::<T>::SessionAlreadyUsed);
+			let encoded = Self::handshake_message(&claim);
+			ensure!(first_signature.verify(&encoded[..], &claim.first) &&
+				second_signature.verify(&encoded[..], &claim.second),
+				Error::<T>::InvalidSignature);
+
+			// Check both edges before writing either.
+			let max_confidence = <Parameters<T>>::get().max_confidence;
+			ensure!(claim.first_confidence <= max_confidence && claim.second_confidence <= max_confidence,
+				Error::<T>::ConfidenceOutOfBounds);
+			ensure!(Self::check_account_validity(claim.first.clone()) &&
+				Self::check_account_validity(claim.second.clone()), Error::<T>::InvalidAttester);
+
+			// Write both attestations.
+			let mut totals = <TotalsCounter<T>>::get().unwrap_or((0, 0));
+			let HandshakeClaim { first, second, first_confidence, second_confidence, session, .. } = claim;
+			Self::apply_attestation(first.clone(), second.clone(), Attestation {
+				confidence: first_confidence,
+				relationship: Relationship::InPersonHandshake,
+				known_for: 0,
+				block: current_block,
+			}, &mut totals);
+			Self::apply_attestation(second.clone(), first.clone(), Attestation {
+				confidence: second_confidence,
+				relationship: Relationship::InPersonHandshake,
+				known_for: 0,
+				block: current_block,
+			}, &mut totals);
+			<TotalsCounter<T>>::put(totals);
+
+			// Use up the session for both parties.
+			<HandshakeSessions<T>>::insert(first.clone(), session, ());
+			<HandshakeSessions<T>>::insert(second.clone(), session, ());
 
-				// Increment the total attest count.	
-				<TotalsCounter<T>>::put((
-					total_attest_count + 1, 
-					sum_confidence + u32::from(confidence.clone()), 
-				));
-
-				if <AccountData<T>>::contains_key(dest.clone()) { // if target destination in AccountData 
-					// Deconstruct latest AccountData for later reference
-					let (og_count, og_confidence, og_birth_block) = <AccountData<T>>::get(dest.clone()).unwrap(); 
-						
-					// Update account data.
-					<AccountData<T>>::insert(dest.clone(), (
-						og_count + 1, // key pair did not exist so add new attestation to the original count 
-						og_confidence + confidence as u32, // add confidence for new attestation to the original sum
-						og_birth_block)); // leave birth block because the account is not new
-
-				} else { // if the account is new initialize all AccountData
-					<AccountData<T>>::insert(dest.clone(), (1, confidence as u32, current_block))
-				}
-			}
-			// Update Attestations.
-			<Attestations<T>>::insert(dest.clone(), origin.clone(), (confidence, current_block));
-			
-			
 			// Emit an event.
-			Self::deposit_event(Event::Attested(origin, dest, (confidence, current_block)));
-			// Return a successful DispatchResultWithPostInfo
+			Self::deposit_event(Event::HandshakeCompleted(first, second, session));
 			Ok(())
 		}
 
 
-		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		/// Attest for several accounts at once, e.g. everyone met at a meetup.
+		/// Every target gets the given confidence with the shared relationship
+		/// and duration. The attester's validity is checked once and the whole
+		/// batch is rejected if any entry is invalid: a confidence out of
+		/// bounds, the origin itself, an unregistered or repeated target.
+		#[pallet::weight(10_000u64.saturating_add(
+			T::DbWeight::get().reads_writes(4, 4).saturating_mul(attestations.len() as u64)
+		))]
+		pub fn attest_many(
+			origin: OriginFor<T>,
+			attestations: BoundedVec<(<T::Lookup as StaticLookup>::Source, Confidence), T::MaxAttestBatch>,
+			relationship: Relationship,
+			known_for: KnownFor,
+		) -> DispatchResult {
+
+			// Check origin is signed and the attester is valid.
+			let origin = ensure_signed(origin)?;
+			ensure!(Self::check_account_validity(origin.clone()), Error::<T>::InvalidAttester);
+
+			// Check every entry before writing anything.
+			let max_confidence = <Parameters<T>>::get().max_confidence;
+			let mut targets: Vec<(T::AccountId, Confidence)> = Vec::with_capacity(attestations.len());
+			for (target, confidence) in attestations.into_iter() {
+				let dest = T::Lookup::lookup(target)?;
+				ensure!(confidence <= max_confidence, Error::<T>::ConfidenceOutOfBounds);
+				ensure!(origin != dest, Error::<T>::SelfAttestationError);
+				ensure!(<AccountData<T>>::contains_key(dest.clone()), Error::<T>::UnregisteredTarget);
+				ensure!(!<RotatingAccounts<T>>::contains_key(dest.clone()), Error::<T>::RotationInProgress);
+				ensure!(!targets.iter().any(|(t, _)| *t == dest), Error::<T>::DuplicateTarget);
+				targets.push((dest, confidence));
+			}
+			ensure!(relationship != Relationship::InPersonHandshake, Error::<T>::ReservedRelationship);
+
+			// Apply all attestations in one pass over the totals.
+			let current_block = <frame_system::Pallet<T>>::block_number();
+			let mut totals = <TotalsCounter<T>>::get().unwrap_or((0, 0));
+			for (dest, confidence) in targets {
+				let attestation = Attestation { confidence, relationship, known_for, block: current_block };
+				Self::apply_attestation(origin.clone(), dest, attestation, &mut totals);
+			}
+			<TotalsCounter<T>>::put(totals);
+
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(12, 9))]
 		/// Begin a challenge on another account's validity. Every account will
 		/// get opportunity to cast a vote to decide whether to keep or ban the
-		/// suspect 
+		/// suspect. The challenger reserves the `challenge_bond`, which is
+		/// returned if the suspect is banned and slashed otherwise. Votes left
+		/// over from the suspect's previous challenge must be cleared first,
+		/// by `on_idle` or `clear_stale_votes`.
 		pub fn challenge(
 			challenger: OriginFor<T>,
 			suspect: <T::Lookup as StaticLookup>::Source,
@@ -283,32 +1210,26 @@ pub mod pallet {
 
 			//Check Challenge Already Created
 			ensure!(!<ActiveChallenges<T>>::contains_key(suspect.clone()), Error::<T>::ChallengeAlreadyExists);
+			ensure!(!<PendingVerdicts<T>>::contains_key(suspect.clone()), Error::<T>::VerdictPending);
+			ensure!(!<StaleVotes<T>>::contains_key(suspect.clone()), Error::<T>::StaleVotesPending);
 
 			// Check challenger validity
 			ensure!(Self::check_account_validity(challenger.clone()), Error::<T>::InvalidChallenger);
 
-			// Calculate final block
-			let current_block = <frame_system::Pallet<T>>::block_number();
-			let final_block = current_block + T::ChallengeDuration::get().into();
-
-			// Add challenge to challenges
-			let mut challenges = <Challenges<T>>::get();
-			match challenges.try_insert(0,(suspect.clone(), final_block)) {
-				Ok(_) => (),
-				Err(_) => return Err(Error::<T>::MaxChallengesReached.into())
-			};
-			<Challenges<T>>::put(challenges);
-			<ActiveChallenges<T>>::insert(suspect.clone(), challenger.clone());
+			// Reserve the bond.
+			let bond = <Parameters<T>>::get().challenge_bond;
+			T::Currency::reserve(&challenger, bond)?;
 
-			// Emit an event.
-			Self::deposit_event(Event::ChallengeCreated(challenger, suspect, final_block));
+			Self::open_challenge(challenger, suspect, bond)?;
 			// Return a successful DispatchResultWithPostInfo
 			Ok(())
 		}
 
 
-		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
-		/// Vote
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(12, 3))]
+		/// Vote on an active challenge: -10..10 (inclusive). Voting again
+		/// replaces the previous vote. At most `MaxVotersPerChallenge` accounts
+		/// can vote on a challenge.
 		pub fn vote(origin: OriginFor<T>, suspect: T::AccountId, value: Vote) -> DispatchResult{
 			let origin = ensure_signed(origin)?;
 
@@ -319,86 +1240,1149 @@ pub mod pallet {
 			// Check voter validity.
 			ensure!(Self::check_account_validity(origin.clone()), Error::<T>::InvalidVoter);
 
+			// Update the running tally, replacing any previous vote.
+			let previous = <Votes<T>>::get(suspect.clone(), origin.clone());
+			let (tally, turnout) = <Tallies<T>>::get(suspect.clone());
+			let (tally, turnout) = match previous {
+				Some(previous) => (tally - previous as i32 + value as i32, turnout),
+				None => {
+					ensure!(turnout < T::MaxVotersPerChallenge::get(), Error::<T>::TooManyVoters);
+					(tally + value as i32, turnout + 1)
+				},
+			};
+			<Tallies<T>>::insert(suspect.clone(), (tally, turnout));
+
 			// Add vote to storage.
 			<Votes<T>>::insert(suspect.clone(), origin.clone(), value);
+			Self::mutate_open_record(suspect.clone(), |record| {
+				if record.status == ChallengeStatus::Evidence {
+					record.status = ChallengeStatus::Voting;
+				}
+			});
 
 			// Emit an event.
-			Self::deposit_event(Event::VoteSubmitted(origin, suspect));
+			Self::deposit_event(Event::VoteSubmitted(origin, suspect, value));
 			// Return a successful DispatchResultWithPostInfo
 			Ok(())
 		}
-	}
 
 
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+		/// Join the social graph. Reserves the `registration_deposit` from the origin
+		/// and creates its AccountData with the current block as its birth
+		/// block. Registering is the only way to create AccountData, so an
+		/// account must register before it can be attested for.
+		pub fn register(origin: OriginFor<T>) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
 
-	// Helper functions.
-	impl<T: Config> Pallet<T> {
+			// Check the account is new and not banned.
+			ensure!(!<AccountData<T>>::contains_key(origin.clone()), Error::<T>::AlreadyRegistered);
+			ensure!(!<BannedAccounts<T>>::contains_key(origin.clone()), Error::<T>::AccountBanned);
+			ensure!(!<RetiredAccounts<T>>::contains_key(origin.clone()), Error::<T>::AccountRetired);
 
-		/// Initializes a block by processing and removing completed challenges. 
-		fn begin_block(block_number: T::BlockNumber) -> Weight {
-			let mut challenges = <Challenges<T>>::get();
+			// Reserve the deposit.
+			let deposit = <Parameters<T>>::get().registration_deposit;
+			T::Currency::reserve(&origin, deposit)?;
 
-			//let mut i: usize = 0;
-			let (mut suspect, mut block) = match challenges.pop() {
-				Some(tup) => tup,
-				None => return T::BlockWeights::get().base_block,
-			};
-			while block > block_number {
-				// Tally votes
-				let tally = Self::tally(suspect.clone());
-
-				// Enact final judgement 
-				if tally < 0 {
-					<BannedAccounts<T>>::insert(suspect.clone(), block_number);
-					// Maybe remove from account data and remove their attestations
-					// Must consider the reprocussions of destroying data like this first
-					// Todo: Remove Old Votes
-				}
+			// Initialize AccountData.
+			let birth_block = <frame_system::Pallet<T>>::block_number();
+			<AccountData<T>>::insert(origin.clone(), (0, 0, birth_block));
+			<RegistrationDeposits<T>>::insert(origin.clone(), deposit);
+			Self::update_candidate(origin.clone());
+
+			// Emit an event.
+			Self::deposit_event(Event::Registered(origin, deposit));
+			Ok(())
+		}
 
-				// Remove from map of active challenges 
-				<ActiveChallenges<T>>::remove(suspect);
 
-				// Prepare for next round
-				(suspect, block) = match challenges.pop() {
-					Some(tup) => tup,
-					None => return T::BlockWeights::get().base_block,
-				};
-				//i += 1;
+		#[pallet::weight(10_000u64
+			.saturating_add(T::DbWeight::get().reads_writes(12, 9))
+			.saturating_add(T::DbWeight::get().reads_writes(6, 5).saturating_mul(*attestations as u64))
+		)]
+		/// Leave the social graph. Removes the origin's AccountData along with
+		/// the attestations it received, and returns its deposit. Attestations
+		/// the origin gave to others are kept. An account cannot leave while it
+		/// is being challenged. `attestations` is the number of attestations
+		/// the origin received, as in its AccountData; it prices the call and
+		/// only the attestations actually removed are charged.
+		pub fn leave(origin: OriginFor<T>, attestations: u32) -> DispatchResultWithPostInfo {
+			let origin = ensure_signed(origin)?;
+
+			// Check the account is registered and not under challenge.
+			let (received, _, _) = <AccountData<T>>::get(origin.clone()).ok_or(Error::<T>::NotRegistered)?;
+			ensure!(received <= attestations, Error::<T>::WitnessTooLow);
+			ensure!(!<ActiveChallenges<T>>::contains_key(origin.clone()), Error::<T>::UnderChallenge);
+			ensure!(!<PendingVerdicts<T>>::contains_key(origin.clone()), Error::<T>::UnderChallenge);
+			ensure!(!<RotatingAccounts<T>>::contains_key(origin.clone()), Error::<T>::RotationInProgress);
+
+			// Remove the received attestations from the totals.
+			let (total_attest_count, sum_confidence) = <TotalsCounter<T>>::get().unwrap_or((0, 0));
+			<AccountData<T>>::remove(origin.clone());
+			let (attest_count, conf_sum) = <Attestations<T>>::drain_prefix(origin.clone())
+				.fold((0, 0), |(count, conf), (attester, attestation)| {
+					Self::note_attestation_removed(origin.clone(), attester.clone());
+					Self::deposit_event(Event::AttestationRemoved(origin.clone(), attester));
+					(count + 1, conf + Self::weighted_confidence(&attestation))
+				});
+			<MutualCount<T>>::remove(origin.clone());
+			Self::clear_recovery(origin.clone());
+			<TotalsCounter<T>>::put((
+				total_attest_count - attest_count,
+				sum_confidence - conf_sum,
+			));
+			Self::update_candidate(origin.clone());
+
+			// Return the deposits.
+			if let Some(profile) = <Profiles<T>>::take(origin.clone()) {
+				T::Currency::unreserve(&origin, profile.deposit);
+				Self::deposit_event(Event::ProfileCleared(origin.clone(), profile.deposit));
 			}
-			match challenges.try_push((suspect, block)) {
-				Ok(_) => (),
-				Err(_) => (), // This should theoretically never happen because we would have just removed a tuple 
-			};
-			<Challenges<T>>::put(challenges);
+			let deposit = <RegistrationDeposits<T>>::take(origin.clone()).unwrap_or_default();
+			T::Currency::unreserve(&origin, deposit);
 
-			T::BlockWeights::get().base_block
+			// Emit an event.
+			Self::deposit_event(Event::Left(origin, deposit));
+			Ok(Some(10_000u64
+				.saturating_add(T::DbWeight::get().reads_writes(12, 9))
+				.saturating_add(T::DbWeight::get().reads_writes(6, 5).saturating_mul(attest_count as u64))
+			).into())
 		}
 
-		/// Checks whether Account is eligible to attest/vote/challenge
-		/// Criteria: 
-		/// 1) Avg confidence is at least = network average
-		/// 2) # attestations is at least = network average
-		/// 3) Average birth_block is at least = network average (to be added)
-		/// 4) Account is not banned from the network 
-		fn check_account_validity(account: T::AccountId) -> bool { 
-			// Retreive necessary data 
-			// Totals
-			let (tot_attest, tot_conf) = match <TotalsCounter<T>>::try_get() {
-				Ok(tup) => tup,
-				Err(_) => {
-					<TotalsCounter<T>>::put((0, 0));
-					(0, 0)
+
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		/// Set the origin's profile: display name, avatar hash, link and a
+		/// public key for out-of-band verification. Only registered, unbanned
+		/// accounts can set a profile. Reserves `ProfileDepositBase` plus
+		/// `ProfileByteDeposit` for each byte of profile data, adjusting any
+		/// deposit already held for a previous profile.
+		pub fn set_profile(
+			origin: OriginFor<T>,
+			display_name: ProfileField<T>,
+			avatar: Option<T::Hash>,
+			link: ProfileField<T>,
+			public_key: ProfileField<T>,
+		) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			// Check the account is a member of the graph.
+			ensure!(<AccountData<T>>::contains_key(origin.clone()), Error::<T>::NotRegistered);
+			ensure!(!<BannedAccounts<T>>::contains_key(origin.clone()), Error::<T>::AccountBanned);
+
+			// Calculate the deposit from the size of the profile.
+			let bytes = display_name.len() + avatar.encoded_size() + link.len() + public_key.len();
+			let deposit = T::ProfileDepositBase::get()
+				.saturating_add(T::ProfileByteDeposit::get().saturating_mul((bytes as u32).into()));
+
+			// Reserve or return the difference with the previous deposit.
+			let old_profile = <Profiles<T>>::get(origin.clone());
+			let old_deposit = old_profile.as_ref().map(|p| p.deposit).unwrap_or_default();
+			if deposit > old_deposit {
+				T::Currency::reserve(&origin, deposit - old_deposit)?;
+			} else {
+				T::Currency::unreserve(&origin, old_deposit - deposit);
+			}
+
+			// Update the profile. Changing an existing profile flags the
+			// attestations made before it.
+			let current_block = <frame_system::Pallet<T>>::block_number();
+			let changed = old_profile.map(|_| current_block);
+			<Profiles<T>>::insert(origin.clone(), Profile {
+				display_name,
+				avatar,
+				link,
+				public_key,
+				deposit,
+				changed,
+			});
+
+			// Emit events.
+			Self::deposit_event(Event::ProfileSet(origin.clone(), deposit));
+			if let Some(block) = changed {
+				Self::deposit_event(Event::ProfileChanged(origin, block));
+			}
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		/// Change the graph's parameters. Only `AdminOrigin` may call this, and
+		/// the values must be within sane bounds: a challenge duration between
+		/// 1 and `MaxChallengeDuration` blocks, a confidence bound between 1 and
+		/// 100, at most 100 required mutual attestations, a veto period of at
+		/// most `MaxChallengeDuration` blocks and a non-zero recovery threshold.
+		pub fn set_parameters(
+			origin: OriginFor<T>,
+			parameters: GraphParameters<T::BlockNumber, BalanceOf<T>>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			// Check the bounds.
+			ensure!(!parameters.challenge_duration.is_zero() &&
+				parameters.challenge_duration <= T::MaxChallengeDuration::get().into(),
+				Error::<T>::InvalidParameters);
+			ensure!(parameters.max_confidence > 0 && parameters.max_confidence <= MAX_CONFIDENCE_LIMIT,
+				Error::<T>::InvalidParameters);
+			ensure!(parameters.min_mutual_attestations <= MIN_MUTUAL_ATTESTATIONS_LIMIT,
+				Error::<T>::InvalidParameters);
+			ensure!(parameters.veto_period <= T::MaxChallengeDuration::get().into(),
+				Error::<T>::InvalidParameters);
+			ensure!(!parameters.recovery_threshold.is_zero(), Error::<T>::InvalidParameters);
+
+			<Parameters<T>>::put(parameters.clone());
+
+			// Emit an event.
+			Self::deposit_event(Event::ParametersSet(parameters));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1 + T::MaxSignedDocuments::get() as u64))]
+		/// Ban an account outside of a challenge. Only `AdminOrigin` may call this.
+		pub fn force_ban(origin: OriginFor<T>, account: T::AccountId, reason: ReasonCode) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(!<BannedAccounts<T>>::contains_key(account.clone()), Error::<T>::AlreadyBanned);
+
+			Self::ban_account(account.clone(), <frame_system::Pallet<T>>::block_number());
+
+			// Emit an event.
+			Self::deposit_event(Event::ForceBanned(account, reason));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			2 + T::MaxSignedDocuments::get() as u64 * (1 + T::MaxDocumentSigners::get() as u64),
+			1 + T::MaxSignedDocuments::get() as u64,
+		))]
+		/// Lift an account's ban. Only `AdminOrigin` may call this. The
+		/// documents it signed are only marked while another signer is banned.
+		pub fn force_unban(origin: OriginFor<T>, account: T::AccountId, reason: ReasonCode) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(<BannedAccounts<T>>::contains_key(account.clone()), Error::<T>::NotBanned);
+
+			<BannedAccounts<T>>::remove(account.clone());
+			Self::update_candidate(account.clone());
+			for hash in <SignedDocuments<T>>::get(account.clone()) {
+				<Documents<T>>::mutate(hash, |record| {
+					if let Some(record) = record {
+						// The earliest ban among the signers that remain banned.
+						record.signer_banned = record.signers.iter()
+							.filter_map(|signer| <BannedAccounts<T>>::get(signer.account.clone()))
+							.min();
+					}
+				});
+				Self::deposit_event(Event::DocumentSignerUnbanned(hash, account.clone()));
+			}
+
+			// Emit an event.
+			Self::deposit_event(Event::ForceUnbanned(account, reason));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(4))]
+		/// Resolve an active challenge now with the given verdict, discarding
+		/// its votes. The challenger's bond is settled as if the vote had
+		/// reached the verdict. Only `AdminOrigin` may call this.
+		pub fn force_resolve_challenge(origin: OriginFor<T>, suspect: T::AccountId, ban: bool) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			Self::remove_challenge(suspect.clone())?;
+			let block_number = <frame_system::Pallet<T>>::block_number();
+			let (tally, turnout) = Self::tally(suspect.clone());
+			Self::record_outcome(suspect.clone(), tally, turnout, ban, block_number);
+			Self::enact_verdict(suspect.clone(), ban, block_number);
+
+			// Emit an event.
+			Self::deposit_event(Event::ChallengeForceResolved(suspect, ban));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(4))]
+		/// Cancel an active challenge without a verdict, discarding its votes
+		/// and returning the challenger's bond. Only `AdminOrigin` may call this.
+		pub fn force_cancel_challenge(origin: OriginFor<T>, suspect: T::AccountId) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			Self::remove_challenge(suspect.clone())?;
+			Self::clear_votes(suspect.clone());
+			let (challenger, bond) = Self::close_challenge(suspect.clone());
+			Self::cancel_record(suspect.clone());
+			if let Some(challenger) = challenger {
+				T::Currency::unreserve(&challenger, bond);
+			}
+
+			// Emit an event.
+			Self::deposit_event(Event::ChallengeForceCancelled(suspect));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(4))]
+		/// Remove an attestation and correct the target's AccountData, the
+		/// totals and mutual counts. Only `AdminOrigin` may call this.
+		pub fn force_remove_attestation(
+			origin: OriginFor<T>,
+			target: T::AccountId,
+			attester: T::AccountId,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let attestation = <Attestations<T>>::take(target.clone(), attester.clone())
+				.ok_or(Error::<T>::AttestationNotFound)?;
+			let weighted = Self::weighted_confidence(&attestation);
+
+			// Correct the counters.
+			Self::note_attestation_removed(target.clone(), attester.clone());
+			let (total_attest_count, sum_confidence) = <TotalsCounter<T>>::get().unwrap_or((0, 0));
+			<TotalsCounter<T>>::put((total_attest_count - 1, sum_confidence - weighted));
+			<AccountData<T>>::mutate(target.clone(), |data| {
+				if let Some((count, conf, _bb)) = data {
+					*count -= 1;
+					*conf -= weighted;
+				}
+			});
+			Self::update_candidate(target.clone());
+
+			// Emit an event.
+			Self::deposit_event(Event::AttestationForceRemoved(target, attester));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(4))]
+		/// Overturn a pending verdict: an acquitted suspect is banned and a
+		/// banned suspect is acquitted, with the challenger's bond settled
+		/// accordingly. Only a motion of the moderation council may call this,
+		/// before the verdict's veto period ends.
+		pub fn overturn_verdict(origin: OriginFor<T>, suspect: T::AccountId) -> DispatchResult {
+			T::CouncilOrigin::ensure_origin(origin)?;
+
+			let verdict = <PendingVerdicts<T>>::take(suspect.clone()).ok_or(Error::<T>::VerdictNotFound)?;
+			let mut queue = <VerdictQueue<T>>::get();
+			queue.retain(|(s, _)| *s != suspect);
+			<VerdictQueue<T>>::put(queue);
+
+			let ban = !verdict.ban;
+			Self::mutate_open_record(suspect.clone(), |record| record.status = ChallengeStatus::Appealed);
+			Self::settle_verdict(suspect.clone(), Some(verdict.challenger), verdict.bond, ban,
+				<frame_system::Pallet<T>>::block_number());
+
+			// Emit an event.
+			Self::deposit_event(Event::VerdictOverturned(suspect, ban));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		/// Remove the origin's profile and return its deposit.
+		pub fn clear_profile(origin: OriginFor<T>) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			let profile = <Profiles<T>>::take(origin.clone()).ok_or(Error::<T>::ProfileNotFound)?;
+			T::Currency::unreserve(&origin, profile.deposit);
+
+			// Emit an event.
+			Self::deposit_event(Event::ProfileCleared(origin, profile.deposit));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(8, 2))]
+		/// Notarize a document by its hash, with optional metadata. Only a
+		/// valid person can notarize, and becomes the document's first signer.
+		/// Its standing right now is recorded with its signature.
+		pub fn notarize(
+			origin: OriginFor<T>,
+			hash: T::Hash,
+			metadata: Option<BoundedVec<u8, T::MaxDocumentMetadataLength>>,
+		) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+			ensure!(!<Documents<T>>::contains_key(hash), Error::<T>::AlreadyNotarized);
+
+			let signer = Self::document_signer(origin.clone())?;
+			let mut signers = BoundedVec::default();
+			signers.try_push(signer).map_err(|_| Error::<T>::TooManySigners)?;
+			Self::note_signed_document(origin.clone(), hash)?;
+			<Documents<T>>::insert(hash, NotaryRecord { metadata, signers, signer_banned: None });
+
+			// Emit an event.
+			Self::deposit_event(Event::DocumentNotarized(hash, origin));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(8, 2))]
+		/// Co-sign a notarized document. Only valid persons can co-sign, and
+		/// their standing is recorded like in `notarize`.
+		pub fn co_sign(origin: OriginFor<T>, hash: T::Hash) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let mut record = <Documents<T>>::get(hash).ok_or(Error::<T>::DocumentNotFound)?;
+			ensure!(!record.signers.iter().any(|s| s.account == origin), Error::<T>::AlreadySigned);
+
+			let signer = Self::document_signer(origin.clone())?;
+			record.signers.try_push(signer).map_err(|_| Error::<T>::TooManySigners)?;
+			Self::note_signed_document(origin.clone(), hash)?;
+			<Documents<T>>::insert(hash, record);
+
+			// Emit an event.
+			Self::deposit_event(Event::DocumentSigned(hash, origin));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			2 + T::MaxDocumentSigners::get() as u64,
+			1 + T::MaxDocumentSigners::get() as u64,
+		))]
+		/// Withdraw the origin's signature from a document, freeing its slot
+		/// in `SignedDocuments`. When the submitter withdraws, the document is
+		/// removed along with every co-signature. A banned signer can't
+		/// withdraw, so the record stays marked.
+		pub fn unsign(origin: OriginFor<T>, hash: T::Hash) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let mut record = <Documents<T>>::get(hash).ok_or(Error::<T>::DocumentNotFound)?;
+			let index = record.signers.iter().position(|s| s.account == origin)
+				.ok_or(Error::<T>::NotSigned)?;
+			ensure!(!<BannedAccounts<T>>::contains_key(origin.clone()), Error::<T>::AccountBanned);
+
+			if index == 0 {
+				<Documents<T>>::remove(hash);
+				for signer in record.signers {
+					<SignedDocuments<T>>::mutate(signer.account, |hashes| hashes.retain(|h| *h != hash));
+				}
+				Self::deposit_event(Event::DocumentRemoved(hash, origin));
+			} else {
+				record.signers.remove(index);
+				<Documents<T>>::insert(hash, record);
+				<SignedDocuments<T>>::mutate(origin.clone(), |hashes| hashes.retain(|h| *h != hash));
+				Self::deposit_event(Event::DocumentUnsigned(hash, origin));
+			}
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 3))]
+		/// Start recovering a lost account. The origin is the new account that
+		/// takes the lost account's place once its attesters confirm. It must
+		/// not be registered itself, and the lost account must be registered,
+		/// unbanned and not under challenge. A recovery that expired is
+		/// replaced.
+		pub fn initiate_recovery(origin: OriginFor<T>, lost: T::AccountId) -> DispatchResult {
+			let rescuer = ensure_signed(origin)?;
+
+			ensure!(!<AccountData<T>>::contains_key(rescuer.clone()), Error::<T>::AlreadyRegistered);
+			ensure!(!<BannedAccounts<T>>::contains_key(rescuer.clone()), Error::<T>::AccountBanned);
+			ensure!(!<RetiredAccounts<T>>::contains_key(rescuer.clone()), Error::<T>::AccountRetired);
+			ensure!(<AccountData<T>>::contains_key(lost.clone()), Error::<T>::NotRegistered);
+			ensure!(!<BannedAccounts<T>>::contains_key(lost.clone()), Error::<T>::AccountBanned);
+			ensure!(<Recoveries<T>>::get(lost.clone()).map_or(true, |recovery| Self::recovery_expired(&recovery)),
+				Error::<T>::RecoveryInProgress);
+			ensure!(!<RotatingAccounts<T>>::contains_key(lost.clone()), Error::<T>::RotationInProgress);
+			ensure!(!<ActiveChallenges<T>>::contains_key(lost.clone()) &&
+				!<PendingVerdicts<T>>::contains_key(lost.clone()), Error::<T>::UnderChallenge);
+
+			// Replace an expired recovery.
+			if <Recoveries<T>>::contains_key(lost.clone()) {
+				Self::clear_recovery(lost.clone());
+				Self::deposit_event(Event::RecoveryCancelled(lost.clone()));
+			}
+
+			<Recoveries<T>>::insert(lost.clone(), ActiveRecovery {
+				rescuer: rescuer.clone(),
+				started: <frame_system::Pallet<T>>::block_number(),
+				confirmed: 0,
+				rejected: 0,
+				threshold_reached: None,
+			});
+
+			// Emit an event.
+			Self::deposit_event(Event::RecoveryInitiated(lost, rescuer));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 2))]
+		/// Confirm the recovery of an account the origin attested for. The
+		/// confirmation counts with the attestation's weighted confidence.
+		/// Once the confirmations reach `recovery_threshold` of the lost
+		/// account's confidence, the `recovery_delay` starts.
+		pub fn confirm_recovery(origin: OriginFor<T>, lost: T::AccountId) -> DispatchResult {
+			let guardian = ensure_signed(origin)?;
+
+			let mut recovery = <Recoveries<T>>::get(lost.clone()).ok_or(Error::<T>::RecoveryNotFound)?;
+			ensure!(!Self::recovery_expired(&recovery), Error::<T>::RecoveryExpired);
+			let weight = Self::guardian_weight(lost.clone(), guardian.clone())?;
+
+			<RecoveryConfirmations<T>>::insert(lost.clone(), guardian.clone(), weight);
+			recovery.confirmed = recovery.confirmed.saturating_add(weight);
+			if recovery.threshold_reached.is_none() && Self::recovery_threshold_met(lost.clone(), recovery.confirmed) {
+				recovery.threshold_reached = Some(<frame_system::Pallet<T>>::block_number());
+			}
+			<Recoveries<T>>::insert(lost.clone(), recovery);
+
+			// Emit an event.
+			Self::deposit_event(Event::RecoveryConfirmed(lost, guardian, weight));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(3))]
+		/// Cancel a recovery of the origin's account. The lost key can do this
+		/// any time before the rescuer claims it.
+		pub fn cancel_recovery(origin: OriginFor<T>) -> DispatchResult {
+			let lost = ensure_signed(origin)?;
+
+			ensure!(<Recoveries<T>>::contains_key(lost.clone()), Error::<T>::RecoveryNotFound);
+			Self::clear_recovery(lost.clone());
+
+			// Emit an event.
+			Self::deposit_event(Event::RecoveryCancelled(lost));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 4))]
+		/// Reject the recovery of an account the origin attested for, e.g.
+		/// because a stranger started it. Rejections count like confirmations
+		/// and cancel the recovery once they reach `recovery_threshold`, so the
+		/// attesters can clear a recovery the lost key can't cancel.
+		pub fn reject_recovery(origin: OriginFor<T>, lost: T::AccountId) -> DispatchResult {
+			let guardian = ensure_signed(origin)?;
+
+			let mut recovery = <Recoveries<T>>::get(lost.clone()).ok_or(Error::<T>::RecoveryNotFound)?;
+			let weight = Self::guardian_weight(lost.clone(), guardian.clone())?;
+
+			<RecoveryRejections<T>>::insert(lost.clone(), guardian.clone(), weight);
+			recovery.rejected = recovery.rejected.saturating_add(weight);
+			Self::deposit_event(Event::RecoveryRejected(lost.clone(), guardian, weight));
+			if Self::recovery_threshold_met(lost.clone(), recovery.rejected) {
+				Self::clear_recovery(lost.clone());
+				Self::deposit_event(Event::RecoveryCancelled(lost));
+			} else {
+				<Recoveries<T>>::insert(lost, recovery);
+			}
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000u64
+			.saturating_add(T::DbWeight::get().reads_writes(16, 14))
+			.saturating_add(T::DbWeight::get().reads_writes(8, 8).saturating_mul(*attestations as u64))
+		)]
+		/// Complete a recovery once its attesters confirmed it and the delay
+		/// passed. The rescuer inherits the attestations the lost account
+		/// received, its AccountData and birth block, and reserves its own
+		/// registration deposit. The lost account's deposits are returned to it
+		/// and it is retired, so it can't register again. Attestations the lost
+		/// account gave to others are kept. `attestations` is the number of
+		/// attestations the lost account received, as in its AccountData; it
+		/// prices the call and only the attestations actually moved are charged.
+		pub fn claim_recovery(
+			origin: OriginFor<T>,
+			lost: T::AccountId,
+			attestations: u32,
+		) -> DispatchResultWithPostInfo {
+			let rescuer = ensure_signed(origin)?;
+
+			let recovery = <Recoveries<T>>::get(lost.clone()).ok_or(Error::<T>::RecoveryNotFound)?;
+			ensure!(recovery.rescuer == rescuer, Error::<T>::NotRescuer);
+			ensure!(Self::recovery_threshold_met(lost.clone(), recovery.confirmed),
+				Error::<T>::ThresholdNotReached);
+			let reached = recovery.threshold_reached.ok_or(Error::<T>::ThresholdNotReached)?;
+			let params = <Parameters<T>>::get();
+			ensure!(<frame_system::Pallet<T>>::block_number() >= reached + params.recovery_delay,
+				Error::<T>::RecoveryDelayPending);
+			ensure!(!<AccountData<T>>::contains_key(rescuer.clone()), Error::<T>::AlreadyRegistered);
+			ensure!(!<ActiveChallenges<T>>::contains_key(lost.clone()) &&
+				!<PendingVerdicts<T>>::contains_key(lost.clone()), Error::<T>::UnderChallenge);
+			ensure!(!<BannedAccounts<T>>::contains_key(lost.clone()), Error::<T>::AccountBanned);
+			let (received, _, _) = <AccountData<T>>::get(lost.clone()).unwrap_or_default();
+			ensure!(received <= attestations, Error::<T>::WitnessTooLow);
+
+			// The rescuer registers with its own deposit.
+			T::Currency::reserve(&rescuer, params.registration_deposit)?;
+			<RegistrationDeposits<T>>::insert(rescuer.clone(), params.registration_deposit);
+
+			Self::clear_recovery(lost.clone());
+
+			// Move the received attestations over to the rescuer. An attestation
+			// the rescuer gave the lost account would become a self-attestation,
+			// so it is dropped.
+			let (count, conf_sum, birth_block) = <AccountData<T>>::take(lost.clone()).unwrap_or_default();
+			let received: Vec<_> = <Attestations<T>>::drain_prefix(lost.clone()).collect();
+			let moved = received.len();
+			let (mut dropped_count, mut dropped_conf) = (0, 0);
+			for (attester, attestation) in received {
+				Self::note_attestation_removed(lost.clone(), attester.clone());
+				Self::deposit_event(Event::AttestationRemoved(lost.clone(), attester.clone()));
+				if attester == rescuer {
+					dropped_count += 1;
+					dropped_conf += Self::weighted_confidence(&attestation);
+					continue;
+				}
+				<Attestations<T>>::insert(rescuer.clone(), attester.clone(), attestation.clone());
+				Self::note_attestation_added(rescuer.clone(), attester.clone());
+				Self::deposit_event(Event::Attested(attester, rescuer.clone(), attestation));
+			}
+			<MutualCount<T>>::remove(lost.clone());
+			<AccountData<T>>::insert(rescuer.clone(), (count - dropped_count, conf_sum - dropped_conf, birth_block));
+			if dropped_count > 0 {
+				let (total_attest_count, sum_confidence) = <TotalsCounter<T>>::get().unwrap_or((0, 0));
+				<TotalsCounter<T>>::put((total_attest_count - dropped_count, sum_confidence - dropped_conf));
+			}
+			Self::update_candidate(lost.clone());
+			Self::update_candidate(rescuer.clone());
+
+			// Retire the lost account and return its deposits.
+			if let Some(profile) = <Profiles<T>>::take(lost.clone()) {
+				T::Currency::unreserve(&lost, profile.deposit);
+			}
+			let deposit = <RegistrationDeposits<T>>::take(lost.clone()).unwrap_or_default();
+			T::Currency::unreserve(&lost, deposit);
+			<RetiredAccounts<T>>::insert(lost.clone(), rescuer.clone());
+
+			// Emit an event.
+			Self::deposit_event(Event::AccountRecovered(lost, rescuer));
+			Ok(Some(10_000u64
+				.saturating_add(T::DbWeight::get().reads_writes(16, 14))
+				.saturating_add(T::DbWeight::get().reads_writes(8, 8).saturating_mul(moved as u64))
+			).into())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			3 * T::MaxChallenges::get() as u64 + 12,
+			T::MaxChallenges::get() as u64 + 10,
+		))]
+		/// Move the origin's identity to a new key. The new key countersigns
+		/// `(ROTATION_CONTEXT, old, new)` so a typo can't hand the identity to
+		/// a wrong account, and must not hold votes on active challenges from
+		/// an earlier registration. AccountData with its birth block, the
+		/// registration deposit, open votes and the challenges the origin
+		/// opened move right away and the old account is retired. Its challenge
+		/// history and the attestations it received and gave are re-keyed over
+		/// the following blocks, a few at a time; until then the new account
+		/// can't attest, vote or be attested for.
+		pub fn rotate_account(
+			origin: OriginFor<T>,
+			new: T::AccountId,
+			signature: T::OffchainSignature,
+		) -> DispatchResult {
+			let old = ensure_signed(origin)?;
+
+			// Check both accounts.
+			ensure!(old != new, Error::<T>::InvalidRotation);
+			let (mut count, mut conf_sum, birth_block) = <AccountData<T>>::get(old.clone())
+				.ok_or(Error::<T>::NotRegistered)?;
+			ensure!(!<BannedAccounts<T>>::contains_key(old.clone()), Error::<T>::AccountBanned);
+			ensure!(!<ActiveChallenges<T>>::contains_key(old.clone()) &&
+				!<PendingVerdicts<T>>::contains_key(old.clone()), Error::<T>::UnderChallenge);
+			ensure!(!<Recoveries<T>>::contains_key(old.clone()), Error::<T>::RecoveryInProgress);
+			ensure!(!<RotatingAccounts<T>>::contains_key(old.clone()), Error::<T>::RotationInProgress);
+			ensure!(!<AccountData<T>>::contains_key(new.clone()), Error::<T>::AlreadyRegistered);
+			ensure!(!<BannedAccounts<T>>::contains_key(new.clone()), Error::<T>::AccountBanned);
+			ensure!(!<RetiredAccounts<T>>::contains_key(new.clone()), Error::<T>::AccountRetired);
+			ensure!(signature.verify(&(ROTATION_CONTEXT, &old, &new).encode()[..], &new),
+				Error::<T>::InvalidSignature);
+			let suspects: Vec<T::AccountId> = <ActiveChallenges<T>>::iter_keys().collect();
+			ensure!(!suspects.iter().any(|suspect| <Votes<T>>::contains_key(suspect, new.clone())),
+				Error::<T>::NewAccountVoted);
+
+			let mut queue = <RotationQueue<T>>::get();
+			queue.try_push((old.clone(), new.clone())).map_err(|_| Error::<T>::TooManyRotations)?;
+			<RotationQueue<T>>::put(queue);
+
+			// Move the deposits held for the old account. Its profile is dropped.
+			let deposit = <RegistrationDeposits<T>>::take(old.clone()).unwrap_or_default();
+			let unmoved = T::Currency::repatriate_reserved(&old, &new, deposit, BalanceStatus::Reserved)?;
+			<RegistrationDeposits<T>>::insert(new.clone(), deposit.saturating_sub(unmoved));
+			if let Some(profile) = <Profiles<T>>::take(old.clone()) {
+				T::Currency::unreserve(&old, profile.deposit);
+				Self::deposit_event(Event::ProfileCleared(old.clone(), profile.deposit));
+			}
+
+			// An attestation the new account gave the old one would become a
+			// self-attestation, so it is dropped.
+			if let Some(attestation) = <Attestations<T>>::get(old.clone(), new.clone()) {
+				Self::note_attestation_removed(old.clone(), new.clone());
+				<Attestations<T>>::remove(old.clone(), new.clone());
+				Self::deposit_event(Event::AttestationRemoved(old.clone(), new.clone()));
+				let weighted = Self::weighted_confidence(&attestation);
+				count -= 1;
+				conf_sum -= weighted;
+				let (total_attest_count, sum_confidence) = <TotalsCounter<T>>::get().unwrap_or((0, 0));
+				<TotalsCounter<T>>::put((total_attest_count - 1, sum_confidence - weighted));
+			}
+
+			// Move AccountData and the mutual count. The mutual pairs carry over
+			// as both directions of each pair are re-keyed.
+			<AccountData<T>>::remove(old.clone());
+			<AccountData<T>>::insert(new.clone(), (count, conf_sum, birth_block));
+			Self::update_candidate(old.clone());
+			Self::update_candidate(new.clone());
+			let mutual = <MutualCount<T>>::take(old.clone());
+			if mutual > 0 {
+				<MutualCount<T>>::insert(new.clone(), mutual);
+			}
+
+			// Move open votes and the challenges the old account opened.
+			for suspect in suspects {
+				if let Some(vote) = <Votes<T>>::take(suspect.clone(), old.clone()) {
+					<Votes<T>>::insert(suspect.clone(), new.clone(), vote);
+				}
+				if <ActiveChallenges<T>>::get(suspect.clone()) == Some(old.clone()) {
+					<ActiveChallenges<T>>::insert(suspect.clone(), new.clone());
+					Self::mutate_open_record(suspect.clone(), |record| record.challenger = new.clone());
+					let bond = <ChallengeBonds<T>>::get(suspect.clone()).unwrap_or_default();
+					let unmoved = T::Currency::repatriate_reserved(&old, &new, bond, BalanceStatus::Reserved)?;
+					<ChallengeBonds<T>>::insert(suspect, bond.saturating_sub(unmoved));
+				}
+			}
+			for suspect in <VerdictQueue<T>>::get().into_iter().map(|(suspect, _)| suspect) {
+				if let Some(mut verdict) = <PendingVerdicts<T>>::get(suspect.clone()) {
+					if verdict.challenger != old {continue};
+					let unmoved = T::Currency::repatriate_reserved(&old, &new, verdict.bond, BalanceStatus::Reserved)?;
+					verdict.challenger = new.clone();
+					verdict.bond = verdict.bond.saturating_sub(unmoved);
+					Self::mutate_open_record(suspect.clone(), |record| record.challenger = new.clone());
+					<PendingVerdicts<T>>::insert(suspect, verdict);
+				}
+			}
+
+			// Retire the old account.
+			<RetiredAccounts<T>>::insert(old.clone(), new.clone());
+			<RotatingAccounts<T>>::insert(new.clone(), old.clone());
+
+			// Emit an event.
+			Self::deposit_event(Event::RotationStarted(old, new));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		/// Withdraw the origin's vote on an active challenge. The challenge
+		/// goes back to `Evidence` once the last vote is withdrawn.
+		pub fn withdraw_vote(origin: OriginFor<T>, suspect: T::AccountId) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			ensure!(<ActiveChallenges<T>>::contains_key(suspect.clone()), Error::<T>::ChallengeNotFound);
+			let vote = <Votes<T>>::take(suspect.clone(), origin.clone()).ok_or(Error::<T>::VoteNotFound)?;
+			let turnout = <Tallies<T>>::mutate(suspect.clone(), |(tally, turnout)| {
+				*tally -= vote as i32;
+				*turnout -= 1;
+				*turnout
+			});
+			if turnout == 0 {
+				Self::mutate_open_record(suspect.clone(), |record| record.status = ChallengeStatus::Evidence);
+			}
+
+			// Emit an event.
+			Self::deposit_event(Event::VoteWithdrawn(origin, suspect));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(5))]
+		/// Cancel a challenge the origin opened. Only possible while nobody but
+		/// the challenger and the suspect voted. Returns `cancel_refund` of the
+		/// bond and slashes the rest.
+		pub fn cancel_challenge(origin: OriginFor<T>, suspect: T::AccountId) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			let challenger = <ActiveChallenges<T>>::get(suspect.clone()).ok_or(Error::<T>::ChallengeNotFound)?;
+			ensure!(challenger == origin, Error::<T>::InvalidChallenger);
+			ensure!(<Votes<T>>::iter_key_prefix(suspect.clone())
+				.all(|voter| voter == origin || voter == suspect), Error::<T>::ChallengeHasVotes);
+
+			Self::remove_challenge(suspect.clone())?;
+			Self::clear_votes(suspect.clone());
+			let (_, bond) = Self::close_challenge(suspect.clone());
+			Self::cancel_record(suspect.clone());
+
+			// Settle the bond.
+			let refund = <Parameters<T>>::get().cancel_refund * bond;
+			T::Currency::unreserve(&origin, refund);
+			let _ = T::Currency::slash_reserved(&origin, bond.saturating_sub(refund));
+
+			// Emit an event.
+			Self::deposit_event(Event::ChallengeCancelled(suspect, refund));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		/// Extend a challenge the origin opened by another `challenge_duration`
+		/// blocks. Only possible once per challenge, and only while fewer than
+		/// `min_turnout` votes were cast.
+		pub fn extend_challenge(origin: OriginFor<T>, suspect: T::AccountId) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			let challenger = <ActiveChallenges<T>>::get(suspect.clone()).ok_or(Error::<T>::ChallengeNotFound)?;
+			ensure!(challenger == origin, Error::<T>::InvalidChallenger);
+			ensure!(!<ExtendedChallenges<T>>::contains_key(suspect.clone()), Error::<T>::AlreadyExtended);
+			let parameters = <Parameters<T>>::get();
+			ensure!(<Tallies<T>>::get(suspect.clone()).1 < parameters.min_turnout,
+				Error::<T>::TurnoutReached);
+
+			// Move the challenge to its new place in the sorted challenges.
+			let mut challenges = <Challenges<T>>::get();
+			let index = challenges.iter().position(|(s, _)| *s == suspect)
+				.ok_or(Error::<T>::ChallengeNotFound)?;
+			let (_, final_block) = challenges.remove(index);
+			let final_block = final_block + parameters.challenge_duration;
+			let index = challenges.iter().position(|(_, block)| *block <= final_block)
+				.unwrap_or(challenges.len());
+			challenges.try_insert(index, (suspect.clone(), final_block))
+				.map_err(|_| Error::<T>::MaxChallengesReached)?;
+			<Challenges<T>>::put(challenges);
+			<ExtendedChallenges<T>>::insert(suspect.clone(), ());
+			Self::mutate_open_record(suspect.clone(), |record| record.final_block = final_block);
+
+			// Emit an event.
+			Self::deposit_event(Event::ChallengeExtended(suspect, final_block));
+			Ok(())
+		}
+
+
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			T::MaxVotersPerChallenge::get() as u64 + 1,
+			T::MaxVotersPerChallenge::get() as u64 + 1,
+		))]
+		/// Delete the stored votes of a suspect's closed challenge. Anyone can
+		/// call this; `on_idle` does the same with spare block weight. Only
+		/// the votes actually deleted are charged.
+		pub fn clear_stale_votes(origin: OriginFor<T>, suspect: T::AccountId) -> DispatchResultWithPostInfo {
+			ensure_signed(origin)?;
+
+			ensure!(<StaleVotes<T>>::take(suspect.clone()).is_some(), Error::<T>::NoStaleVotes);
+			let removed = <Votes<T>>::drain_prefix(suspect).count() as u64;
+			Ok(Some(10_000 + T::DbWeight::get().reads_writes(removed + 1, removed + 1)).into())
+		}
+	}
+
+
+
+	// Helper functions.
+	impl<T: Config> Pallet<T> {
+
+		/// The bytes an attester signs for `attest_signed`. The context and
+		/// the genesis hash keep the signature from being valid for any other
+		/// message or chain.
+		pub fn attestation_message(payload: &AttestationPayload<T::AccountId, T::BlockNumber>) -> Vec<u8> {
+			let genesis_hash = <frame_system::Pallet<T>>::block_hash(T::BlockNumber::zero());
+			(ATTESTATION_CONTEXT, genesis_hash, payload).encode()
+		}
+
+		/// The bytes both parties sign for `handshake`, tagged like
+		/// `attestation_message` but with their own context.
+		pub fn handshake_message(claim: &HandshakeClaim<T::AccountId, T::BlockNumber>) -> Vec<u8> {
+			let genesis_hash = <frame_system::Pallet<T>>::block_hash(T::BlockNumber::zero());
+			(HANDSHAKE_CONTEXT, genesis_hash, claim).encode()
+		}
+
+		/// Initializes a block by processing and removing completed challenges. 
+		fn begin_block(block_number: T::BlockNumber) -> Weight {
+			// Verdicts whose veto period passed are enacted first, so a challenge
+			// finishing this block with no veto period doesn't wait a block.
+			let mut queue = <VerdictQueue<T>>::get();
+			if !queue.is_empty() {
+				while let Some((suspect, block)) = queue.last().cloned() {
+					if block > block_number {break};
+					queue.pop();
+					if let Some(verdict) = <PendingVerdicts<T>>::take(suspect.clone()) {
+						Self::settle_verdict(suspect.clone(), Some(verdict.challenger), verdict.bond,
+							verdict.ban, block_number);
+						Self::deposit_event(Event::VerdictEnacted(suspect, verdict.ban));
+					}
 				}
+				<VerdictQueue<T>>::put(queue);
+			}
+
+			let mut challenges = <Challenges<T>>::get();
+			if challenges.is_empty() {
+				return T::BlockWeights::get().base_block
+			}
+
+			// Challenges are sorted by final block, so the finished ones are at the back.
+			while let Some((suspect, block)) = challenges.last().cloned() {
+				if block > block_number {break};
+				challenges.pop();
+				Self::resolve_challenge(suspect, block_number);
+			}
+			<Challenges<T>>::put(challenges);
+
+			T::BlockWeights::get().base_block
+		}
+
+		/// Tallies a finished challenge and reaches a verdict. The verdict waits
+		/// for the council's veto period before it is enacted.
+		fn resolve_challenge(suspect: T::AccountId, block_number: T::BlockNumber) {
+			// Tally votes
+			let (tally, turnout) = Self::tally(suspect.clone());
+			let ban = tally < 0;
+			Self::record_outcome(suspect.clone(), tally, turnout, ban, block_number);
+
+			// Remove from map of active challenges 
+			let (challenger, bond) = match Self::close_challenge(suspect.clone()) {
+				(Some(challenger), bond) => (challenger, bond),
+				(None, _) => return,
+			};
+
+			// Give the council a window to overturn the verdict. Without one,
+			// enact the final judgement right away. The queue has room, as
+			// `open_challenge` counts pending verdicts against `MaxChallenges`.
+			let veto_period = <Parameters<T>>::get().veto_period;
+			if !veto_period.is_zero() {
+				let finalize_at = block_number + veto_period;
+				let mut queue = <VerdictQueue<T>>::get();
+				let index = queue.iter().position(|(_, block)| *block <= finalize_at)
+					.unwrap_or(queue.len());
+				if queue.try_insert(index, (suspect.clone(), finalize_at)).is_ok() {
+					<VerdictQueue<T>>::put(queue);
+					<PendingVerdicts<T>>::insert(suspect.clone(),
+						PendingVerdict { challenger, bond, ban, finalize_at });
+					Self::deposit_event(Event::VerdictPending(suspect, ban, finalize_at));
+					return
+				}
+				frame_support::defensive!("VerdictQueue has room for every finished challenge");
+			}
+			Self::settle_verdict(suspect.clone(), Some(challenger), bond, ban, block_number);
+			Self::deposit_event(Event::VerdictEnacted(suspect, ban));
+		}
+
+		/// Removes a challenge from the map of active challenges and enacts the
+		/// verdict right away.
+		fn enact_verdict(suspect: T::AccountId, ban: bool, block_number: T::BlockNumber) {
+			// Remove from map of active challenges 
+			let (challenger, bond) = Self::close_challenge(suspect.clone());
+			Self::settle_verdict(suspect, challenger, bond, ban, block_number);
+		}
+
+		/// Removes a challenge from the map of active challenges along with its
+		/// bond and extension, returning the challenger and the bond.
+		fn close_challenge(suspect: T::AccountId) -> (Option<T::AccountId>, BalanceOf<T>) {
+			<ExtendedChallenges<T>>::remove(suspect.clone());
+			let challenger = <ActiveChallenges<T>>::take(suspect.clone());
+			let bond = <ChallengeBonds<T>>::take(suspect).unwrap_or_default();
+			(challenger, bond)
+		}
+
+		/// Bans the suspect if the verdict says so and settles the challenger's bond.
+		fn settle_verdict(
+			suspect: T::AccountId,
+			challenger: Option<T::AccountId>,
+			bond: BalanceOf<T>,
+			ban: bool,
+			block_number: T::BlockNumber,
+		) {
+			// The verdict takes effect, closing the challenge's record.
+			if let Some(id) = <OpenChallenges<T>>::take(suspect.clone()) {
+				<ChallengeRecords<T>>::mutate(id, |record| {
+					if let Some(outcome) = record.as_mut().and_then(|r| r.outcome.as_mut()) {
+						outcome.enacted = Some(block_number);
+					}
+				});
+			}
+
+			if ban {
+				Self::ban_account(suspect.clone(), block_number);
+				// Maybe remove from account data and remove their attestations
+				// Must consider the reprocussions of destroying data like this first
+
+				// The challenge was right, return the bond.
+				if let Some(challenger) = challenger {
+					T::Currency::unreserve(&challenger, bond);
+				}
+			} else if let Some(challenger) = challenger {
+				// The challenge failed, slash the bond.
+				let _ = T::Currency::slash_reserved(&challenger, bond);
+			}
+		}
+
+		/// Removes an active challenge from the `Challenges` queue before its
+		/// final block.
+		fn remove_challenge(suspect: T::AccountId) -> DispatchResult {
+			let mut challenges = <Challenges<T>>::get();
+			let index = challenges.iter().position(|(s, _)| *s == suspect)
+				.ok_or(Error::<T>::ChallengeNotFound)?;
+			challenges.remove(index);
+			<Challenges<T>>::put(challenges);
+			Ok(())
+		}
+
+		/// Re-keys up to `RotationStepsPerBlock` attestations and history
+		/// entries of the first rotation in the queue: first the attestations
+		/// the old account received, then the challenges it was the suspect
+		/// and the challenger of, then the attestations it gave, found by
+		/// scanning the registered accounts from where the last block stopped.
+		/// Moving an attestation changes neither its weight nor its pair, so
+		/// `TotalsCounter` and the mutual counts stay as they are.
+		fn process_rotation() -> Weight {
+			let mut queue = <RotationQueue<T>>::get();
+			let (old, new) = match queue.first().cloned() {
+				Some(rotation) => rotation,
+				None => return T::DbWeight::get().reads(1),
+			};
+			let steps = T::RotationStepsPerBlock::get() as usize;
+
+			// Attestations the old account received.
+			let received: Vec<_> = <Attestations<T>>::drain_prefix(old.clone()).take(steps).collect();
+			let mut budget = steps - received.len();
+			for (attester, attestation) in received {
+				<Attestations<T>>::insert(new.clone(), attester.clone(), attestation.clone());
+				Self::deposit_event(Event::AttestationRemoved(old.clone(), attester.clone()));
+				Self::deposit_event(Event::Attested(attester, new.clone(), attestation));
+			}
+			if budget == 0 {
+				return T::DbWeight::get().reads_writes(steps as u64 + 1, 2 * steps as u64)
+			}
+
+			// Challenge history of the old account.
+			let challenged: Vec<_> = <ChallengesBySuspect<T>>::drain_prefix(old.clone()).take(budget).collect();
+			budget -= challenged.len();
+			for (id, _) in challenged {
+				<ChallengesBySuspect<T>>::insert(new.clone(), id, ());
+			}
+			let opened: Vec<_> = <ChallengesByChallenger<T>>::drain_prefix(old.clone()).take(budget).collect();
+			budget -= opened.len();
+			for (id, _) in opened {
+				<ChallengesByChallenger<T>>::insert(new.clone(), id, ());
+			}
+			if budget == 0 {
+				return T::DbWeight::get().reads_writes(steps as u64 + 1, 2 * steps as u64)
+			}
+
+			// Attestations the old account gave, one registered account per step.
+			let targets: Vec<T::AccountId> = match <RotationCursor<T>>::get() {
+				Some(last) => <AccountData<T>>::iter_keys_from(<AccountData<T>>::hashed_key_for(last))
+					.take(budget).collect(),
+				None => <AccountData<T>>::iter_keys().take(budget).collect(),
 			};
+			budget -= targets.len();
+			for target in targets.iter() {
+				if let Some(attestation) = <Attestations<T>>::take(target.clone(), old.clone()) {
+					<Attestations<T>>::insert(target.clone(), new.clone(), attestation.clone());
+					Self::deposit_event(Event::AttestationRemoved(target.clone(), old.clone()));
+					Self::deposit_event(Event::Attested(new.clone(), target.clone(), attestation));
+				}
+			}
+
+			if budget > 0 {
+				// Every account was scanned, the rotation is complete.
+				queue.remove(0);
+				<RotationQueue<T>>::put(queue);
+				<RotationCursor<T>>::kill();
+				<RotatingAccounts<T>>::remove(new.clone());
+				Self::deposit_event(Event::AccountRotated(old, new));
+			} else if let Some(last) = targets.last() {
+				<RotationCursor<T>>::put(last);
+			}
+
+			T::DbWeight::get().reads_writes(2 * steps as u64 + 2, 2 * steps as u64 + 3)
+		}
+
+
+		/// Elects the moderation council: the `CouncilSize` valid accounts with
+		/// the highest confidence sum, excluding suspects of open challenges.
+		/// Only the `CouncilCandidates` are considered, so the election is
+		/// bounded by `MaxCouncilCandidates`. Candidates that were banned or
+		/// left are replaced first by ranking every account again.
+		fn elect_council() -> Weight {
+			let mut weight = 0;
+			if <CouncilCandidates<T>>::decode_len().unwrap_or(0) < T::MaxCouncilCandidates::get() as usize {
+				weight = Self::rank_candidates();
+			}
+			let candidates = <CouncilCandidates<T>>::get();
+			let considered = candidates.len() as u64;
+
+			// Candidates are sorted highest standing first.
+			let mut members: Vec<T::AccountId> = candidates.into_iter()
+				.map(|(account, _, _)| account)
+				.filter(|account| {
+					!<ActiveChallenges<T>>::contains_key(account) &&
+						!<PendingVerdicts<T>>::contains_key(account) &&
+						Self::check_account_validity(account.clone())
+				})
+				.take(T::CouncilSize::get() as usize)
+				.collect();
+			members.sort();
+
+			let old_members = <CouncilMembers<T>>::get();
+			T::Council::set_members_sorted(&members, &old_members);
+			<CouncilMembers<T>>::put(BoundedVec::try_from(members.clone()).unwrap_or_default());
+
+			Self::deposit_event(Event::CouncilElected(members));
+			weight.saturating_add(T::DbWeight::get().reads_writes(6 * considered + 3, 2))
+		}
+
+		/// Rebuilds `CouncilCandidates` from every registered, unbanned
+		/// account. Ties are broken by account ID.
+		fn rank_candidates() -> Weight {
+			let mut accounts = 0u64;
+			let mut ranked: Vec<(T::AccountId, ConfidenceSum, AttestCount)> = <AccountData<T>>::iter()
+				.inspect(|_| accounts += 1)
+				.filter(|(account, _)| !<BannedAccounts<T>>::contains_key(account))
+				.map(|(account, (attest_count, conf_sum, _))| (account, conf_sum, attest_count))
+				.collect();
+			ranked.sort_by(|a, b| (b.1, b.2).cmp(&(a.1, a.2)).then_with(|| a.0.cmp(&b.0)));
+			ranked.truncate(T::MaxCouncilCandidates::get() as usize);
+			<CouncilCandidates<T>>::put(BoundedVec::try_from(ranked).unwrap_or_default());
+			T::DbWeight::get().reads_writes(2 * accounts + 1, 1)
+		}
+
+		/// Re-ranks an account among the council candidates after its
+		/// AccountData or ban changed. Unregistered and banned accounts drop
+		/// out; others are kept if they rank within `MaxCouncilCandidates`.
+		pub(crate) fn update_candidate(account: T::AccountId) {
+			let mut candidates = <CouncilCandidates<T>>::get();
+			candidates.retain(|(candidate, _, _)| *candidate != account);
+
+			if let Some((attest_count, conf_sum, _)) = <AccountData<T>>::get(account.clone()) {
+				if !<BannedAccounts<T>>::contains_key(account.clone()) {
+					let index = candidates.iter()
+						.position(|(_, conf, count)| (*conf, *count) < (conf_sum, attest_count))
+						.unwrap_or(candidates.len());
+					if index < T::MaxCouncilCandidates::get() as usize {
+						if candidates.len() == T::MaxCouncilCandidates::get() as usize {
+							candidates.pop();
+						}
+						let _ = candidates.try_insert(index, (account, conf_sum, attest_count));
+					}
+				}
+			}
+
+			<CouncilCandidates<T>>::put(candidates);
+		}
+
+		/// Discards all votes on a challenge. The votes are only marked for
+		/// deletion, which happens lazily.
+		fn clear_votes(suspect: T::AccountId) {
+			<Tallies<T>>::remove(suspect.clone());
+			if <Votes<T>>::iter_key_prefix(suspect.clone()).next().is_some() {
+				<StaleVotes<T>>::insert(suspect, ());
+			}
+		}
+
+		/// Checks whether Account is eligible to attest/vote/challenge
+		/// Criteria: 
+		/// 0) Account is registered
+		/// 1) Avg confidence is at least = network average
+		/// 2) # attestations is at least = network average
+		/// 3) Average birth_block is at least = network average (to be added)
+		/// 4) Account is not banned from the network 
+		/// This is a pure read: it never writes to storage.
+		fn check_account_validity(account: T::AccountId) -> bool { 
+			// Retreive necessary data 
+			// Totals
+			let (tot_attest, tot_conf) = <TotalsCounter<T>>::get().unwrap_or((0, 0));
 			let tot_accounts = <AccountData<T>>::count();
 			// Account
-			let (attest_count, conf_sum, _bb) = match <AccountData<T>>::try_get(account.clone()) {
-				Ok(tup) => tup,
-				Err(_) => {
-					let birth_block =  <frame_system::Pallet<T>>::block_number();
-					<AccountData<T>>::insert(account.clone(), (0, 0, birth_block));
-					(0, 0, birth_block)
-				}
+			let (attest_count, conf_sum, _bb) = match <AccountData<T>>::get(account.clone()) {
+				Some(tup) => tup,
+				None => return false,
 			};
 			
 			// If there are no attestations let the network start
@@ -413,22 +2397,538 @@ pub mod pallet {
 			let avg_attest = tot_attest / tot_accounts;
 			if attest_count < avg_attest {return false};
 
+			// # mutual attestations is within the required bounds
+			let parameters = <Parameters<T>>::get();
+			let mutual = <MutualCount<T>>::get(account.clone());
+			if mutual < parameters.min_mutual_attestations {return false};
+			if mutual > parameters.max_mutual_share.mul_ceil(attest_count) {return false};
+
 			// Average birth_block is at least = network average (to be added)
 			//let avg_bb = sum_bb / tot_accounts;
 			//if bb <= avg_bb {return false};
 
 			// Account is not banned from the network 
-			if <BannedAccounts<T>>::contains_key(account) {return false};
+			if <BannedAccounts<T>>::contains_key(account.clone()) {return false};
+
+			// Account is not halfway through a rotation
+			if <RotatingAccounts<T>>::contains_key(account) {return false};
 
 			// If everything passes return true
 			true
 		}
 
 
-		/// Tallies the votes from a challenge
-		fn tally(suspect: T::AccountId) -> i32{
-			<Votes<T>>::drain_prefix(suspect)
-				.map(|vote| vote.1 as i32).sum()
+		/// Records a new attestation from `attester` to `target`. If the target
+		/// already attested for the attester the pair becomes mutual.
+		fn note_attestation_added(target: T::AccountId, attester: T::AccountId) {
+			if <Attestations<T>>::contains_key(attester.clone(), target.clone()) {
+				<MutualCount<T>>::mutate(target.clone(), |count| *count += 1);
+				<MutualCount<T>>::mutate(attester.clone(), |count| *count += 1);
+				Self::deposit_event(Event::MutualAttestation(attester, target));
+			}
+		}
+
+
+		/// Records the removal of the attestation from `attester` to `target`.
+		/// If the target attested for the attester the pair stops being mutual.
+		fn note_attestation_removed(target: T::AccountId, attester: T::AccountId) {
+			if <Attestations<T>>::contains_key(attester.clone(), target.clone()) {
+				<MutualCount<T>>::mutate(target.clone(), |count| *count = count.saturating_sub(1));
+				<MutualCount<T>>::mutate(attester.clone(), |count| *count = count.saturating_sub(1));
+				Self::deposit_event(Event::MutualAttestationRemoved(attester, target));
+			}
+		}
+
+
+		/// An attestation's confidence weighted by its relationship. This is
+		/// what counts towards AccountData and TotalsCounter.
+		pub fn weighted_confidence(attestation: &Attestation<T::BlockNumber>) -> ConfidenceSum {
+			u32::from(attestation.confidence)
+				.saturating_mul(T::RelationshipWeight::convert(attestation.relationship))
+		}
+
+
+		/// Whether an attestation was made before the target last changed its
+		/// profile, i.e. it vouched for a previous identity.
+		pub fn attestation_predates_profile(target: T::AccountId, attester: T::AccountId) -> bool {
+			match (<Attestations<T>>::get(target.clone(), attester), <Profiles<T>>::get(target)) {
+				(Some(attestation), Some(Profile { changed: Some(changed), .. })) =>
+					attestation.block < changed,
+				_ => false,
+			}
+		}
+
+
+		/// Checks and writes a single attestation from `attester` to `dest`.
+		fn do_attest(
+			attester: T::AccountId,
+			dest: T::AccountId,
+			confidence: Confidence,
+			relationship: Relationship,
+			known_for: KnownFor,
+		) -> DispatchResult {
+
+			// Ensure that confidence is within the valid range 0..max_confidence (inclusive).
+			ensure!(confidence <= <Parameters<T>>::get().max_confidence, Error::<T>::ConfidenceOutOfBounds);
+
+			// Ensure that attester and dest are not the same account.
+			ensure!(attester.clone() != dest.clone(), Error::<T>::SelfAttestationError);
+
+			// Handshake edges need the other party's signature.
+			ensure!(relationship != Relationship::InPersonHandshake, Error::<T>::ReservedRelationship);
+			
+			// Retrieve the current block number for later reference
+			let current_block = <frame_system::Pallet<T>>::block_number();
+
+			// Get latest attest count
+			let mut totals = <TotalsCounter<T>>::get().unwrap_or((0, 0));
+
+			// Ensure attester is valid
+			ensure!(Self::check_account_validity(attester.clone()), Error::<T>::InvalidAttester);
+
+			// Only registered accounts have AccountData, so the target must have registered.
+			ensure!(<AccountData<T>>::contains_key(dest.clone()), Error::<T>::UnregisteredTarget);
+			ensure!(!<RotatingAccounts<T>>::contains_key(dest.clone()), Error::<T>::RotationInProgress);
+
+			let attestation = Attestation { confidence, relationship, known_for, block: current_block };
+			Self::apply_attestation(attester, dest, attestation, &mut totals);
+			<TotalsCounter<T>>::put(totals);
+
+			// Return a successful DispatchResult
+			Ok(())
+		}
+
+
+		/// Writes an attestation to a registered target, updates its AccountData
+		/// and the given running totals, and emits `Attested`. The caller checks
+		/// the attestation and writes the totals back.
+		fn apply_attestation(
+			attester: T::AccountId,
+			target: T::AccountId,
+			attestation: Attestation<T::BlockNumber>,
+			totals: &mut (TotalAttestations, SumAllConfidence),
+		) {
+			// Deconstruct latest AccountData for later reference.
+			let (og_count, og_confidence, birth_block) = match <AccountData<T>>::get(target.clone()) {
+				Some(data) => data,
+				None => return,
+			};
+			let weighted = Self::weighted_confidence(&attestation);
+
+			// Update storage (Attestations and Account Data).
+
+			if let Some(old) = <Attestations<T>>::get(target.clone(), attester.clone()) { // if attestations contains the key pair already it means we're chanching values of an existing attestation
+				let old_weighted = Self::weighted_confidence(&old);
+
+				// Swap the old confidence for the new one in the totals.
+				totals.1 = totals.1 - old_weighted + weighted;
+
+				// Update account data.
+				<AccountData<T>>::insert(target.clone(), (
+					og_count, // do not increment because key pair already exists  
+					og_confidence - old_weighted + weighted, // swap old confidence for new
+					birth_block)); // leave birth block unchainged 
+
+			} else { // if the key pair doesn't exist yet, this is a new attestation
+
+				// Pair up with the reverse attestation if the target already attested for the origin.
+				Self::note_attestation_added(target.clone(), attester.clone());
+
+				// Increment the total attest count.
+				totals.0 += 1;
+				totals.1 += weighted;
+
+				// Update account data.
+				<AccountData<T>>::insert(target.clone(), (
+					og_count + 1, // key pair did not exist so add new attestation to the original count 
+					og_confidence + weighted, // add confidence for new attestation to the original sum
+					birth_block)); // leave birth block because the account is not new
+			}
+			// Update Attestations.
+			<Attestations<T>>::insert(target.clone(), attester.clone(), attestation.clone());
+			Self::update_candidate(target.clone());
+
+			// Emit an event.
+			Self::deposit_event(Event::Attested(attester, target, attestation));
+		}
+
+
+		/// Snapshots a valid person's standing for a document signature.
+		fn document_signer(
+			account: T::AccountId,
+		) -> Result<DocumentSigner<T::AccountId, T::BlockNumber>, DispatchError> {
+			let (attest_count, confidence, _) = <AccountData<T>>::get(account.clone())
+				.ok_or(Error::<T>::InvalidSigner)?;
+			ensure!(!<BannedAccounts<T>>::contains_key(account.clone()), Error::<T>::InvalidSigner);
+			ensure!(Self::check_account_validity(account.clone()), Error::<T>::InvalidSigner);
+			Ok(DocumentSigner {
+				account,
+				block: <frame_system::Pallet<T>>::block_number(),
+				attest_count,
+				confidence,
+			})
+		}
+
+
+		/// Remembers that an account signed a document.
+		fn note_signed_document(account: T::AccountId, hash: T::Hash) -> DispatchResult {
+			<SignedDocuments<T>>::try_mutate(account, |hashes| {
+				hashes.try_push(hash).map_err(|_| Error::<T>::TooManySignedDocuments.into())
+			})
+		}
+
+
+		/// Bans an account and marks every document it signed.
+		fn ban_account(account: T::AccountId, block_number: T::BlockNumber) {
+			<BannedAccounts<T>>::insert(account.clone(), block_number);
+			Self::update_candidate(account.clone());
+
+			for hash in <SignedDocuments<T>>::get(account.clone()) {
+				<Documents<T>>::mutate(hash, |record| {
+					if let Some(record) = record {
+						record.signer_banned.get_or_insert(block_number);
+					}
+				});
+				Self::deposit_event(Event::DocumentSignerBanned(hash, account.clone()));
+			}
+		}
+
+
+		/// Whether a recovery ran out of time to reach its threshold.
+		fn recovery_expired(recovery: &ActiveRecovery<T::AccountId, T::BlockNumber>) -> bool {
+			recovery.threshold_reached.is_none() &&
+				<frame_system::Pallet<T>>::block_number() >= recovery.started.saturating_add(T::RecoveryPeriod::get())
+		}
+
+
+		/// Checks that `guardian` can confirm or reject the recovery of `lost`
+		/// and returns the weight it counts with: the weighted confidence of
+		/// its attestation for the lost account.
+		fn guardian_weight(lost: T::AccountId, guardian: T::AccountId) -> Result<ConfidenceSum, DispatchError> {
+			let attestation = <Attestations<T>>::get(lost.clone(), guardian.clone())
+				.ok_or(Error::<T>::NotAGuardian)?;
+			ensure!(Self::check_account_validity(guardian.clone()), Error::<T>::NotAGuardian);
+			ensure!(!<RecoveryConfirmations<T>>::contains_key(lost.clone(), guardian.clone()) &&
+				!<RecoveryRejections<T>>::contains_key(lost, guardian), Error::<T>::AlreadyConfirmed);
+			Ok(Self::weighted_confidence(&attestation))
+		}
+
+
+		/// Removes the recovery of `lost` along with its confirmations and
+		/// rejections.
+		fn clear_recovery(lost: T::AccountId) {
+			<Recoveries<T>>::remove(lost.clone());
+			<RecoveryConfirmations<T>>::drain_prefix(lost.clone()).for_each(drop);
+			<RecoveryRejections<T>>::drain_prefix(lost).for_each(drop);
+		}
+
+
+		/// Whether the confirmed weight reaches `recovery_threshold` of the lost
+		/// account's current weighted confidence.
+		fn recovery_threshold_met(lost: T::AccountId, confirmed: ConfidenceSum) -> bool {
+			let (_, conf_sum, _) = <AccountData<T>>::get(lost).unwrap_or_default();
+			confirmed > 0 && confirmed >= <Parameters<T>>::get().recovery_threshold.mul_ceil(conf_sum)
+		}
+
+
+		/// Takes the running tally of a challenge, returning the sum and count
+		/// of its votes. The votes themselves are left for lazy cleanup.
+		fn tally(suspect: T::AccountId) -> (i32, u32) {
+			let tally = <Tallies<T>>::get(suspect.clone());
+			Self::clear_votes(suspect);
+			tally
+		}
+
+
+		/// Deletes the votes of closed challenges until `limit` weight is used.
+		fn clear_stale_votes_within(limit: Weight) -> Weight {
+			let db = T::DbWeight::get();
+			let per_vote = db.reads_writes(1, 1).max(1);
+			let mut used = db.reads(1);
+			while used < limit {
+				let suspect = match <StaleVotes<T>>::iter_keys().next() {
+					Some(suspect) => suspect,
+					None => break,
+				};
+				let max = (limit.saturating_sub(used) / per_vote) as usize;
+				if max == 0 {break};
+				let removed = <Votes<T>>::drain_prefix(suspect.clone()).take(max).count();
+				used = used.saturating_add(per_vote.saturating_mul(removed as u64 + 1));
+				if removed < max {
+					<StaleVotes<T>>::remove(suspect);
+				}
+			}
+			used
+		}
+
+
+		/// Applies `f` to the record of the suspect's open challenge.
+		fn mutate_open_record(
+			suspect: T::AccountId,
+			f: impl FnOnce(&mut ChallengeRecord<T::AccountId, T::BlockNumber>),
+		) {
+			if let Some(id) = <OpenChallenges<T>>::get(suspect) {
+				<ChallengeRecords<T>>::mutate(id, |record| record.as_mut().map(f));
+			}
+		}
+
+
+		/// Records the verdict of the suspect's open challenge and emits
+		/// `ChallengeResolved`. The record stays open until the verdict is enacted.
+		fn record_outcome(suspect: T::AccountId, tally: i32, turnout: u32, banned: bool, block_number: T::BlockNumber) {
+			let outcome = ChallengeOutcome { tally, turnout, banned, resolved: block_number, enacted: None };
+			Self::mutate_open_record(suspect.clone(), |record| {
+				record.status = ChallengeStatus::Resolved;
+				record.outcome = Some(outcome.clone());
+			});
+			if let Some(id) = <OpenChallenges<T>>::get(suspect.clone()) {
+				Self::deposit_event(Event::ChallengeResolved(id, suspect, outcome));
+			}
+		}
+
+
+		/// Opens a challenge of `suspect` and keeps a record of it. The caller
+		/// checks both parties and has reserved the challenger's bond.
+		fn open_challenge(challenger: T::AccountId, suspect: T::AccountId, bond: BalanceOf<T>) -> DispatchResult {
+			let id = <NextChallengeId<T>>::get();
+			let next_id = id.checked_add(1).ok_or(Error::<T>::ChallengeIdOverflow)?;
+
+			// Calculate final block
+			let parameters = <Parameters<T>>::get();
+			let current_block = <frame_system::Pallet<T>>::block_number();
+			let final_block = current_block + parameters.challenge_duration;
+
+			// Add challenge to challenges, which are kept sorted by final block
+			// with the earliest at the back. Pending verdicts count towards the
+			// limit too, so every challenge can wait out its veto period.
+			let mut challenges = <Challenges<T>>::get();
+			let pending = <VerdictQueue<T>>::decode_len().unwrap_or(0);
+			ensure!(challenges.len() + pending < T::MaxChallenges::get() as usize,
+				Error::<T>::MaxChallengesReached);
+			let index = challenges.iter().position(|(_, block)| *block <= final_block)
+				.unwrap_or(challenges.len());
+			match challenges.try_insert(index, (suspect.clone(), final_block)) {
+				Ok(_) => (),
+				Err(_) => return Err(Error::<T>::MaxChallengesReached.into())
+			};
+
+			// Challenges opened at genesis have no bond.
+			if !bond.is_zero() {
+				<ChallengeBonds<T>>::insert(suspect.clone(), bond);
+			}
+
+			<Challenges<T>>::put(challenges);
+			<ActiveChallenges<T>>::insert(suspect.clone(), challenger.clone());
+
+			// Keep a record of the challenge.
+			<NextChallengeId<T>>::put(next_id);
+			<ChallengeRecords<T>>::insert(id, ChallengeRecord {
+				suspect: suspect.clone(),
+				challenger: challenger.clone(),
+				status: ChallengeStatus::Evidence,
+				opened: current_block,
+				final_block,
+				outcome: None,
+			});
+			<OpenChallenges<T>>::insert(suspect.clone(), id);
+			<ChallengesBySuspect<T>>::insert(suspect.clone(), id, ());
+			<ChallengesByChallenger<T>>::insert(challenger.clone(), id, ());
+
+			// Emit an event.
+			Self::deposit_event(Event::ChallengeCreated(id, challenger, suspect, final_block));
+			Ok(())
+		}
+
+
+		/// Marks the suspect's open challenge as cancelled and closes it.
+		fn cancel_record(suspect: T::AccountId) {
+			Self::mutate_open_record(suspect.clone(), |record| record.status = ChallengeStatus::Cancelled);
+			<OpenChallenges<T>>::remove(suspect);
+		}
+	}
+
+
+	// Runtime API.
+	impl<T: Config> Pallet<T> {
+
+		/// The part of `graph_stats` kept in counters: accounts, attestations,
+		/// their confidence, active challenges and pending verdicts. The other
+		/// fields are left at zero. Cheap enough to read on every block.
+		pub fn graph_totals() -> GraphStats {
+			let (total_attestations, total_confidence) = <TotalsCounter<T>>::get().unwrap_or((0, 0));
+			GraphStats {
+				accounts: <AccountData<T>>::count(),
+				total_attestations,
+				total_confidence,
+				active_challenges: <Challenges<T>>::decode_len().unwrap_or(0) as u32,
+				pending_verdicts: <VerdictQueue<T>>::decode_len().unwrap_or(0) as u32,
+				..Default::default()
+			}
+		}
+
+		/// Summarizes the graph for the node's metrics. Bans count as recent
+		/// when they happened within `window` blocks of the current block.
+		/// This walks every account and challenge record, so it is only meant
+		/// to be called off-chain through the runtime API.
+		pub fn graph_stats(window: T::BlockNumber) -> GraphStats {
+			let mut stats = Self::graph_totals();
+
+			stats.valid_accounts = <AccountData<T>>::iter_keys()
+				.filter(|account| Self::check_account_validity(account.clone()))
+				.count() as u32;
+
+			// Bans within the window
+			let since = <frame_system::Pallet<T>>::block_number().saturating_sub(window);
+			for (_, block) in <BannedAccounts<T>>::iter() {
+				stats.banned_accounts += 1;
+				if block >= since {stats.recent_bans += 1};
+			}
+
+			// Outcomes of the challenges that closed. A resolved verdict still
+			// in its veto period hasn't closed yet.
+			for (_, record) in <ChallengeRecords<T>>::iter() {
+				match (record.status, record.outcome) {
+					(ChallengeStatus::Resolved, Some(outcome)) if outcome.enacted.is_some() => {
+						if outcome.banned {
+							stats.challenges_banned += 1;
+						} else {
+							stats.challenges_cleared += 1;
+						}
+					},
+					(ChallengeStatus::Appealed, _) => stats.challenges_appealed += 1,
+					(ChallengeStatus::Cancelled, _) => stats.challenges_cancelled += 1,
+					_ => {},
+				}
+			}
+
+			stats
+		}
+
+		/// Whether `account` currently passes the validity check to attest,
+		/// vote and challenge.
+		pub fn is_valid(account: T::AccountId) -> bool {
+			Self::check_account_validity(account)
+		}
+	}
+
+
+	// Try-runtime checks.
+	#[cfg(any(feature = "try-runtime", test))]
+	impl<T: Config> Pallet<T> {
+
+		/// Checks that the derived storage of the social graph is consistent:
+		/// 1) `TotalsCounter` equals the sum over `AccountData`
+		/// 2) Each account's count and confidence sum equal its `Attestations`
+		///    prefix, and its `MutualCount` its reversed attestations
+		/// 3) `Challenges` and `ActiveChallenges` hold the same set of suspects
+		/// 4) No `Votes` are left over for inactive challenges
+		///
+		/// FRAME v0.9.26 has no `try_state` hook, so the node's `try-runtime`
+		/// subcommand only reaches these checks through `post_upgrade`, i.e.
+		/// with `on-runtime-upgrade` against a chain snapshot.
+		pub fn do_try_state() -> Result<(), &'static str> {
+			// 1) Totals match the sum over all accounts.
+			let (tot_attest, tot_conf) = <TotalsCounter<T>>::get().unwrap_or((0, 0));
+			let (sum_attest, sum_conf) = <AccountData<T>>::iter_values()
+				.fold((0u64, 0u64), |(count, conf), (attest_count, conf_sum, _)| {
+					(count + attest_count as u64, conf + conf_sum as u64)
+				});
+			ensure!(sum_attest == tot_attest as u64,
+				"TotalsCounter attestations do not match the sum over AccountData");
+			ensure!(sum_conf == tot_conf as u64,
+				"TotalsCounter confidence does not match the sum over AccountData");
+
+			// 2) Each account matches its attestations prefix. Attestations of
+			// a rotation in progress are split between both keys, so this is
+			// only checked once all rotations completed.
+			if <RotationQueue<T>>::get().is_empty() {
+				for (account, (attest_count, conf_sum, _)) in <AccountData<T>>::iter() {
+					let (count, conf, mutual) = <Attestations<T>>::iter_prefix(account.clone())
+						.fold((0u32, 0u32, 0u32), |(count, conf, mutual), (attester, attestation)| {
+							let reversed = <Attestations<T>>::contains_key(attester, account.clone());
+							(count + 1, conf + Self::weighted_confidence(&attestation), mutual + reversed as u32)
+						});
+					ensure!(count == attest_count,
+						"AccountData count does not match the account's attestations");
+					ensure!(conf == conf_sum,
+						"AccountData confidence does not match the account's attestations");
+					ensure!(mutual == <MutualCount<T>>::get(account),
+						"MutualCount does not match the account's mutual attestations");
+				}
+				ensure!(<Attestations<T>>::iter_keys()
+					.all(|(target, _)| <AccountData<T>>::contains_key(target)),
+					"Attestation target is missing from AccountData");
+			}
+
+			// 3) Challenges and ActiveChallenges hold the same set.
+			let challenges = <Challenges<T>>::get();
+			let suspects: BTreeSet<T::AccountId> = challenges.iter()
+				.map(|(suspect, _)| suspect.clone()).collect();
+			ensure!(suspects.len() == challenges.len(), "Duplicate suspect in Challenges");
+			let active: BTreeSet<T::AccountId> = <ActiveChallenges<T>>::iter_keys().collect();
+			ensure!(suspects == active, "Challenges and ActiveChallenges are out of sync");
+			ensure!(<ChallengeBonds<T>>::iter_keys()
+				.all(|suspect| <ActiveChallenges<T>>::contains_key(suspect)),
+				"Bond left over for an inactive challenge");
+			ensure!(<ExtendedChallenges<T>>::iter_keys()
+				.all(|suspect| <ActiveChallenges<T>>::contains_key(suspect)),
+				"Extension left over for an inactive challenge");
+
+			// Pending verdicts and their queue hold the same set.
+			let queue = <VerdictQueue<T>>::get();
+			let queued: BTreeSet<T::AccountId> = queue.iter()
+				.map(|(suspect, _)| suspect.clone()).collect();
+			ensure!(queued.len() == queue.len(), "Duplicate suspect in VerdictQueue");
+			let pending: BTreeSet<T::AccountId> = <PendingVerdicts<T>>::iter_keys().collect();
+			ensure!(queued == pending, "VerdictQueue and PendingVerdicts are out of sync");
+			ensure!(pending.is_disjoint(&active), "Suspect has both a challenge and a pending verdict");
+
+			// Exactly the active challenges and pending verdicts have an open record.
+			let open: BTreeSet<T::AccountId> = <OpenChallenges<T>>::iter_keys().collect();
+			ensure!(open == active.union(&pending).cloned().collect(),
+				"OpenChallenges does not match the active challenges and pending verdicts");
+			for (suspect, id) in <OpenChallenges<T>>::iter() {
+				let record = <ChallengeRecords<T>>::get(id).ok_or("Open challenge without a record")?;
+				ensure!(record.suspect == suspect, "Open challenge record of another suspect");
+				let expected = if active.contains(&suspect) {
+					record.status == ChallengeStatus::Evidence || record.status == ChallengeStatus::Voting
+				} else {
+					record.status == ChallengeStatus::Resolved
+				};
+				ensure!(expected, "Open challenge record has the wrong status");
+			}
+
+			// Council candidates are unbanned, up to date and ranked.
+			let candidates = <CouncilCandidates<T>>::get();
+			for (account, conf_sum, attest_count) in candidates.iter() {
+				let (count, conf, _) = <AccountData<T>>::get(account).ok_or("Unregistered council candidate")?;
+				ensure!((count, conf) == (*attest_count, *conf_sum), "Council candidate standing is stale");
+				ensure!(!<BannedAccounts<T>>::contains_key(account), "Banned council candidate");
+			}
+			ensure!(candidates.windows(2).all(|pair| (pair[0].1, pair[0].2) >= (pair[1].1, pair[1].2)),
+				"Council candidates are not ranked");
+			let ranked: BTreeSet<T::AccountId> = candidates.iter().map(|(account, _, _)| account.clone()).collect();
+			ensure!(ranked.len() == candidates.len(), "Duplicate council candidate");
+
+			// Recoveries only exist for registered accounts.
+			ensure!(<Recoveries<T>>::iter_keys().all(|lost| <AccountData<T>>::contains_key(lost)),
+				"Recovery of an unregistered account");
+
+			// 4) Votes of inactive challenges are marked for cleanup and the
+			// running tallies match the votes.
+			ensure!(<Votes<T>>::iter_keys().all(|(suspect, _)| {
+				<ActiveChallenges<T>>::contains_key(suspect.clone()) || <StaleVotes<T>>::contains_key(suspect)
+			}), "Votes left over for an inactive challenge");
+			ensure!(<Tallies<T>>::iter_keys().all(|suspect| active.contains(&suspect)),
+				"Tally left over for an inactive challenge");
+			for suspect in active.iter() {
+				let tally = <Votes<T>>::iter_prefix_values(suspect.clone())
+					.fold((0i32, 0u32), |(tally, turnout), vote| (tally + vote as i32, turnout + 1));
+				ensure!(tally == <Tallies<T>>::get(suspect.clone()), "Tally does not match the votes");
+				ensure!(tally.1 <= T::MaxVotersPerChallenge::get(), "Too many voters on a challenge");
+			}
+
+			Ok(())
 		}
 	}
 }
\ No newline at end of file
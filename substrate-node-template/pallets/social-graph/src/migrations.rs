@@ -0,0 +1,246 @@
+//! Storage migrations for the social graph pallet.
+
+use super::*;
+use frame_support::{
+	traits::{Get, GetStorageVersion, StorageVersion},
+	weights::Weight,
+};
+
+/// Version 1 records how the attester knows the target on each attestation.
+pub mod v1 {
+	use super::*;
+
+	/// Translates `Attestations` from `(confidence, block)` tuples to
+	/// [`Attestation`]s with an unspecified relationship, then recomputes
+	/// `AccountData` and `TotalsCounter` from the weighted confidences.
+	pub fn migrate<T: Config>() -> Weight {
+		let on_chain = Pallet::<T>::on_chain_storage_version();
+		if on_chain >= 1 {
+			return T::DbWeight::get().reads(1)
+		}
+
+		// Translate attestations.
+		let mut attestations = 0u64;
+		<Attestations<T>>::translate::<(u8, T::BlockNumber), _>(|_, _, (confidence, block)| {
+			attestations += 1;
+			Some(Attestation { confidence, relationship: Relationship::Unspecified, known_for: 0, block })
+		});
+
+		// Recompute account data and totals.
+		let mut accounts = 0u64;
+		let (mut tot_attest, mut tot_conf) = (0u32, 0u32);
+		<AccountData<T>>::translate::<(u32, u32, T::BlockNumber), _>(|account, (_, _, birth_block)| {
+			accounts += 1;
+			let (count, conf) = <Attestations<T>>::iter_prefix_values(account)
+				.fold((0u32, 0u32), |(count, conf), attestation| {
+					(count + 1, conf + Pallet::<T>::weighted_confidence(&attestation))
+				});
+			tot_attest += count;
+			tot_conf += conf;
+			Some((count, conf, birth_block))
+		});
+		<TotalsCounter<T>>::put((tot_attest, tot_conf));
+
+		StorageVersion::new(1).put::<Pallet<T>>();
+
+		T::DbWeight::get().reads_writes(2 * attestations + accounts + 1, attestations + accounts + 2)
+	}
+}
+
+/// Version 2 gives every challenge an ID and a record that outlives it.
+pub mod v2 {
+	use super::*;
+
+	/// Creates records for the challenges that are active or awaiting their
+	/// verdict. Their opening block and tally were not stored before, so the
+	/// opening block is estimated from the current challenge duration and
+	/// pending verdicts are recorded with an empty tally.
+	pub fn migrate<T: Config>() -> Weight {
+		let on_chain = Pallet::<T>::on_chain_storage_version();
+		if on_chain >= 2 {
+			return T::DbWeight::get().reads(1)
+		}
+
+		let parameters = <Parameters<T>>::get();
+		let mut next_id = <NextChallengeId<T>>::get();
+		let mut records = 0u64;
+		let mut open = |suspect: T::AccountId, record: ChallengeRecord<T::AccountId, T::BlockNumber>| {
+			<ChallengesBySuspect<T>>::insert(suspect.clone(), next_id, ());
+			<ChallengesByChallenger<T>>::insert(record.challenger.clone(), next_id, ());
+			<OpenChallenges<T>>::insert(suspect, next_id);
+			<ChallengeRecords<T>>::insert(next_id, record);
+			next_id += 1;
+			records += 1;
+		};
+
+		for (suspect, final_block) in <Challenges<T>>::get() {
+			let challenger = match <ActiveChallenges<T>>::get(suspect.clone()) {
+				Some(challenger) => challenger,
+				None => continue,
+			};
+			let status = if <Votes<T>>::iter_prefix(suspect.clone()).next().is_some() {
+				ChallengeStatus::Voting
+			} else {
+				ChallengeStatus::Evidence
+			};
+			open(suspect.clone(), ChallengeRecord {
+				suspect,
+				challenger,
+				status,
+				opened: final_block.saturating_sub(parameters.challenge_duration),
+				final_block,
+				outcome: None,
+			});
+		}
+
+		for (suspect, verdict) in <PendingVerdicts<T>>::iter() {
+			let resolved = verdict.finalize_at.saturating_sub(parameters.veto_period);
+			open(suspect.clone(), ChallengeRecord {
+				suspect,
+				challenger: verdict.challenger,
+				status: ChallengeStatus::Resolved,
+				opened: resolved.saturating_sub(parameters.challenge_duration),
+				final_block: resolved,
+				outcome: Some(ChallengeOutcome {
+					tally: 0,
+					turnout: 0,
+					banned: verdict.ban,
+					resolved,
+					enacted: None,
+				}),
+			});
+		}
+		<NextChallengeId<T>>::put(next_id);
+
+		StorageVersion::new(2).put::<Pallet<T>>();
+
+		T::DbWeight::get().reads_writes(2 * records + 3, 4 * records + 2)
+	}
+}
+
+/// Version 3 keeps a running tally of each active challenge and deletes the
+/// votes of closed challenges lazily.
+pub mod v3 {
+	use super::*;
+
+	/// Computes the tallies of the active challenges from their votes.
+	pub fn migrate<T: Config>() -> Weight {
+		let on_chain = Pallet::<T>::on_chain_storage_version();
+		if on_chain >= 3 {
+			return T::DbWeight::get().reads(1)
+		}
+
+		let mut votes = 0u64;
+		for (suspect, _, vote) in <Votes<T>>::iter() {
+			votes += 1;
+			if <ActiveChallenges<T>>::contains_key(suspect.clone()) {
+				<Tallies<T>>::mutate(suspect, |(tally, turnout)| {
+					*tally += vote as i32;
+					*turnout += 1;
+				});
+			} else {
+				<StaleVotes<T>>::insert(suspect, ());
+			}
+		}
+
+		StorageVersion::new(3).put::<Pallet<T>>();
+
+		T::DbWeight::get().reads_writes(3 * votes + 1, votes + 1)
+	}
+}
+
+/// Version 4 ranks the council candidates instead of electing from the whole
+/// graph.
+pub mod v4 {
+	use super::*;
+
+	/// Ranks every registered account once.
+	pub fn migrate<T: Config>() -> Weight {
+		let on_chain = Pallet::<T>::on_chain_storage_version();
+		if on_chain >= 4 {
+			return T::DbWeight::get().reads(1)
+		}
+
+		let mut accounts = 0u64;
+		for account in <AccountData<T>>::iter_keys() {
+			accounts += 1;
+			Pallet::<T>::update_candidate(account);
+		}
+
+		StorageVersion::new(4).put::<Pallet<T>>();
+
+		T::DbWeight::get().reads_writes(4 * accounts + 1, accounts + 1)
+	}
+}
+
+/// Version 5 lets the attesters of a lost account reject its recovery.
+pub mod v5 {
+	use super::*;
+	use codec::Decode;
+
+	/// `ActiveRecovery` before version 5, without the rejected weight.
+	#[derive(Decode)]
+	struct OldActiveRecovery<AccountId, BlockNumber> {
+		rescuer: AccountId,
+		started: BlockNumber,
+		confirmed: ConfidenceSum,
+		threshold_reached: Option<BlockNumber>,
+	}
+
+	/// Translates the ongoing recoveries, with nothing rejected yet.
+	pub fn migrate<T: Config>() -> Weight {
+		let on_chain = Pallet::<T>::on_chain_storage_version();
+		if on_chain >= 5 {
+			return T::DbWeight::get().reads(1)
+		}
+
+		let mut recoveries = 0u64;
+		<Recoveries<T>>::translate::<OldActiveRecovery<T::AccountId, T::BlockNumber>, _>(|_, old| {
+			recoveries += 1;
+			Some(ActiveRecovery {
+				rescuer: old.rescuer,
+				started: old.started,
+				confirmed: old.confirmed,
+				rejected: 0,
+				threshold_reached: old.threshold_reached,
+			})
+		});
+
+		StorageVersion::new(5).put::<Pallet<T>>();
+
+		T::DbWeight::get().reads_writes(recoveries + 1, recoveries + 1)
+	}
+}
+
+/// Version 6 counts the mutual attestations that existed before `MutualCount`.
+pub mod v6 {
+	use super::*;
+	use sp_std::collections::btree_map::BTreeMap;
+
+	/// Recomputes `MutualCount` from `Attestations`: each account counts the
+	/// accounts it both attested for and was attested by.
+	pub fn migrate<T: Config>() -> Weight {
+		let on_chain = Pallet::<T>::on_chain_storage_version();
+		if on_chain >= 6 {
+			return T::DbWeight::get().reads(1)
+		}
+
+		let mut attestations = 0u64;
+		let mut counts: BTreeMap<T::AccountId, u32> = BTreeMap::new();
+		for (target, attester) in <Attestations<T>>::iter_keys() {
+			attestations += 1;
+			if <Attestations<T>>::contains_key(attester, target.clone()) {
+				*counts.entry(target).or_default() += 1;
+			}
+		}
+		let removed = <MutualCount<T>>::drain().count() as u64;
+		let inserted = counts.len() as u64;
+		for (account, count) in counts {
+			<MutualCount<T>>::insert(account, count);
+		}
+
+		StorageVersion::new(6).put::<Pallet<T>>();
+
+		T::DbWeight::get().reads_writes(2 * attestations + removed + 1, removed + inserted + 1)
+	}
+}
@@ -1,10 +1,12 @@
 use crate as social_graph;
-use frame_support::traits::{ConstU16, ConstU32, ConstU64};
+use frame_support::traits::{ConstU16, ConstU32, ConstU64, GenesisBuild};
+use frame_system::EnsureRoot;
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
-	testing::Header,
-	traits::{BlakeTwo256, IdentityLookup},
+	testing::{Header, TestSignature, UintAuthorityId},
+	traits::{BlakeTwo256, Convert, IdentityLookup},
+	Percent,
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -18,7 +20,8 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-		SocialGraph: social_graph::{Pallet, Call, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		SocialGraph: social_graph::{Pallet, Call, Config<T>, Storage, Event<T>},
 	}
 );
 
@@ -40,7 +43,7 @@ impl system::Config for Test {
 	type BlockHashCount = ConstU64<250>;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
@@ -49,13 +52,91 @@ impl system::Config for Test {
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+// Online-only attestations count half as much as any other relationship.
+pub struct RelationshipWeight;
+
+impl Convert<social_graph::Relationship, u32> for RelationshipWeight {
+	fn convert(relationship: social_graph::Relationship) -> u32 {
+		match relationship {
+			social_graph::Relationship::OnlineOnly => 1,
+			social_graph::Relationship::InPersonHandshake => 3,
+			_ => 2,
+		}
+	}
+}
+
 impl social_graph::Config for Test {
 	type Event = Event;
-	type ChallengeDuration = ConstU32<8>;
+	type MaxChallengeDuration = ConstU32<100>;
 	type MaxChallenges = ConstU32<1_000>;
+	type MaxVotersPerChallenge = ConstU32<3>;
+	type MaxAttestBatch = ConstU32<4>;
+	type Currency = Balances;
+	type OffchainSignature = TestSignature;
+	type OffchainPublic = UintAuthorityId;
+	type AdminOrigin = EnsureRoot<u64>;
+	type MaxProfileFieldLength = ConstU32<16>;
+	type ProfileDepositBase = ConstU64<5>;
+	type ProfileByteDeposit = ConstU64<1>;
+	type RelationshipWeight = RelationshipWeight;
+	type MaxPendingRotations = ConstU32<4>;
+	type RotationStepsPerBlock = ConstU32<2>;
+	type MaxDocumentMetadataLength = ConstU32<16>;
+	type MaxDocumentSigners = ConstU32<3>;
+	type MaxSignedDocuments = ConstU32<4>;
+	type RecoveryPeriod = ConstU64<30>;
+	type Council = ();
+	type CouncilOrigin = EnsureRoot<u64>;
+	type CouncilSize = ConstU32<3>;
+	type CouncilTerm = ConstU64<20>;
+	type MaxCouncilCandidates = ConstU32<5>;
+}
+
+// Parameters the mock runtime starts with.
+pub fn test_parameters() -> social_graph::GraphParameters<u64, u64> {
+	social_graph::GraphParameters {
+		challenge_duration: 8,
+		max_confidence: 10,
+		min_mutual_attestations: 0,
+		max_mutual_share: Percent::from_percent(100),
+		registration_deposit: 10,
+		challenge_bond: 0,
+		veto_period: 0,
+		recovery_threshold: Percent::from_percent(50),
+		recovery_delay: 10,
+		cancel_refund: Percent::from_percent(50),
+		min_turnout: 2,
+	}
 }
 
 // Build genesis storage according to the mock runtime.
+// Accounts 0..20 are endowed with 100 units each.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: (0..20).map(|i| (i, 100)).collect() }
+		.assimilate_storage(&mut t)
+		.unwrap();
+	social_graph::GenesisConfig::<Test> { parameters: test_parameters(), ..Default::default() }
+		.assimilate_storage(&mut t)
+		.unwrap();
+	t.into()
+}
+
+// Register accounts in the social graph.
+pub fn register(accounts: impl IntoIterator<Item = u64>) {
+	for account in accounts {
+		SocialGraph::register(Origin::signed(account)).unwrap();
+	}
 }
@@ -1,10 +1,14 @@
 use crate::{mock::*, Error};
-use frame_support::{assert_noop, assert_ok};
+use frame_support::{assert_noop, assert_ok, traits::{GenesisBuild, Hooks}};
+use codec::Encode;
+use sp_core::H256;
+use sp_runtime::{testing::TestSignature, traits::BadOrigin};
 use crate::*;
 
 #[test]
 fn attest_test() {
     new_test_ext().execute_with(|| {
+        register(0..12);
         // Dispatch a signed extrinsic.
         // Regular attest works for all valid confidence
         for i in 0..11 {
@@ -22,12 +26,12 @@ fn attest_test() {
 				Err(_) => println!("Nothing In Account data for {}", account.clone()),
 			};
 
-            assert_ok!(SocialGraph::attest(Origin::signed(i), i+1, i.try_into().unwrap()));
+            assert_ok!(SocialGraph::attest(Origin::signed(i), i+1, i.try_into().unwrap(), Relationship::MetInPerson, 12));
         }
         // Test invalid confidence
-        assert_noop!(SocialGraph::attest(Origin::signed(2), 1, 11), Error::<Test>::ConfidenceOutOfBounds);  
+        assert_noop!(SocialGraph::attest(Origin::signed(2), 1, 11, Relationship::MetInPerson, 12), Error::<Test>::ConfidenceOutOfBounds);  
         // Test invalid target
-        assert_noop!(SocialGraph::attest(Origin::signed(2), 2, 1), Error::<Test>::SelfAttestationError);   
+        assert_noop!(SocialGraph::attest(Origin::signed(2), 2, 1, Relationship::MetInPerson, 12), Error::<Test>::SelfAttestationError);   
         println!("{:?}", <AccountData<Test>>::get(3));
     });
 }
@@ -35,6 +39,7 @@ fn attest_test() {
 #[test]
 fn challenge_and_vote_test() {
     new_test_ext().execute_with(|| {
+        register(1..4);
         // Dispatch a signed extrinsic.
         assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
         assert_noop!(SocialGraph::challenge(Origin::signed(1), 2), Error::<Test>::ChallengeAlreadyExists);
@@ -47,3 +52,964 @@ fn challenge_and_vote_test() {
         assert_noop!(SocialGraph::vote(Origin::signed(3), 1, 0), Error::<Test>::ChallengeNotFound);
     });
 }
+
+#[test]
+fn try_state_test() {
+    new_test_ext().execute_with(|| {
+        // Empty graph is consistent
+        assert_ok!(SocialGraph::do_try_state());
+        register(0..6);
+
+        // Attestations, challenges and votes keep derived storage consistent
+        for i in 0..5 {
+            assert_ok!(SocialGraph::attest(Origin::signed(i), i+1, 5, Relationship::MetInPerson, 12));
+        }
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_ok!(SocialGraph::vote(Origin::signed(1), 2, 3));
+        assert_ok!(SocialGraph::do_try_state());
+
+        // Corrupted totals are detected
+        <TotalsCounter<Test>>::put((0, 0));
+        assert!(SocialGraph::do_try_state().is_err());
+    });
+}
+
+#[test]
+fn register_and_leave_test() {
+    new_test_ext().execute_with(|| {
+        // Registering reserves the deposit and creates AccountData
+        assert_ok!(SocialGraph::register(Origin::signed(1)));
+        assert_eq!(Balances::reserved_balance(1), 10);
+        assert_eq!(<AccountData<Test>>::get(1), Some((0, 0, 0)));
+        assert_noop!(SocialGraph::register(Origin::signed(1)), Error::<Test>::AlreadyRegistered);
+
+        // Only registered accounts can be attested for
+        assert_noop!(SocialGraph::attest(Origin::signed(1), 2, 5, Relationship::MetInPerson, 12), Error::<Test>::UnregisteredTarget);
+        // A failed validity check leaves no state behind
+        assert_noop!(SocialGraph::attest(Origin::signed(3), 1, 5, Relationship::MetInPerson, 12), Error::<Test>::InvalidAttester);
+
+        // Leaving removes received attestations and returns the deposit
+        register([2]);
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 2, 5, Relationship::MetInPerson, 12));
+        assert_noop!(SocialGraph::leave(Origin::signed(2), 0), Error::<Test>::WitnessTooLow);
+        assert_ok!(SocialGraph::leave(Origin::signed(2), 1));
+        assert_eq!(<AccountData<Test>>::get(2), None);
+        assert_eq!(<Attestations<Test>>::get(2, 1), None);
+        assert_eq!(<TotalsCounter<Test>>::get(), Some((0, 0)));
+        assert_eq!(Balances::reserved_balance(2), 0);
+        assert_noop!(SocialGraph::leave(Origin::signed(2), 0), Error::<Test>::NotRegistered);
+        assert_ok!(SocialGraph::do_try_state());
+
+        // Challenged accounts cannot leave
+        register([3]);
+        assert_ok!(SocialGraph::challenge(Origin::signed(3), 1));
+        assert_noop!(SocialGraph::leave(Origin::signed(1), 0), Error::<Test>::UnderChallenge);
+    });
+}
+
+#[test]
+fn profile_test() {
+    new_test_ext().execute_with(|| {
+        let field = |bytes: &[u8]| ProfileField::<Test>::try_from(bytes.to_vec()).unwrap();
+
+        // Only members can set a profile
+        assert_noop!(
+            SocialGraph::set_profile(Origin::signed(1), field(b"alice"), None, field(b""), field(b"")),
+            Error::<Test>::NotRegistered
+        );
+        register([1, 2]);
+
+        // Deposit scales with the size of the profile: 5 + 1 per byte
+        assert_ok!(SocialGraph::set_profile(Origin::signed(1), field(b"alice"), None, field(b"a.io"), field(b"")));
+        assert_eq!(Balances::reserved_balance(1), 10 + 5 + 10);
+        assert_eq!(SocialGraph::profiles(1).unwrap().changed, None);
+
+        // Changing the profile adjusts the deposit and flags older attestations
+        assert_ok!(SocialGraph::attest(Origin::signed(2), 1, 7, Relationship::MetInPerson, 12));
+        System::set_block_number(3);
+        assert_ok!(SocialGraph::set_profile(Origin::signed(1), field(b"al"), None, field(b""), field(b"")));
+        assert_eq!(Balances::reserved_balance(1), 10 + 5 + 3);
+        assert_eq!(SocialGraph::profiles(1).unwrap().changed, Some(3));
+        assert!(SocialGraph::attestation_predates_profile(1, 2));
+
+        // Clearing returns the deposit
+        assert_ok!(SocialGraph::clear_profile(Origin::signed(1)));
+        assert_eq!(Balances::reserved_balance(1), 10);
+        assert_noop!(SocialGraph::clear_profile(Origin::signed(1)), Error::<Test>::ProfileNotFound);
+    });
+}
+
+#[test]
+fn relationship_weight_test() {
+    new_test_ext().execute_with(|| {
+        register(1..4);
+
+        // Online-only attestations count half as much
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 2, 6, Relationship::OnlineOnly, 3));
+        assert_ok!(SocialGraph::attest(Origin::signed(2), 1, 6, Relationship::Family, 240));
+        assert_eq!(<AccountData<Test>>::get(2).map(|data| data.1), Some(6));
+        assert_eq!(<AccountData<Test>>::get(1).map(|data| data.1), Some(12));
+        assert_eq!(<TotalsCounter<Test>>::get(), Some((2, 18)));
+
+        // The relationship is stored with the attestation
+        let attestation = <Attestations<Test>>::get(2, 1).unwrap();
+        assert_eq!(attestation.relationship, Relationship::OnlineOnly);
+        assert_eq!(attestation.known_for, 3);
+
+        // Changing the relationship reweighs the existing attestation
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 2, 6, Relationship::Colleague, 3));
+        assert_eq!(<AccountData<Test>>::get(2).map(|data| data.1), Some(12));
+        assert_eq!(<TotalsCounter<Test>>::get(), Some((2, 24)));
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn mutual_attestation_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..4);
+
+        // One-way attestations are not mutual
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 2, 5, Relationship::MetInPerson, 12));
+        assert_eq!(SocialGraph::mutual_count(1), 0);
+        assert_eq!(SocialGraph::mutual_count(2), 0);
+
+        // Attesting back makes the pair mutual
+        assert_ok!(SocialGraph::attest(Origin::signed(2), 1, 5, Relationship::MetInPerson, 12));
+        assert_eq!(SocialGraph::mutual_count(1), 1);
+        assert_eq!(SocialGraph::mutual_count(2), 1);
+        System::assert_has_event(Event::SocialGraph(crate::Event::MutualAttestation(2, 1)));
+
+        // Overwriting an attestation doesn't count the pair twice
+        assert_ok!(SocialGraph::attest(Origin::signed(2), 1, 6, Relationship::MetInPerson, 12));
+        assert_eq!(SocialGraph::mutual_count(1), 1);
+        assert_ok!(SocialGraph::do_try_state());
+
+        // Leaving breaks the pair
+        assert_ok!(SocialGraph::leave(Origin::signed(2), 1));
+        assert_eq!(SocialGraph::mutual_count(1), 0);
+        System::assert_has_event(Event::SocialGraph(crate::Event::MutualAttestationRemoved(1, 2)));
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn mutual_count_migration_test() {
+    use frame_support::traits::{GetStorageVersion, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        register(1..4);
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 2, 5, Relationship::MetInPerson, 12));
+        assert_ok!(SocialGraph::attest(Origin::signed(2), 1, 5, Relationship::MetInPerson, 12));
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 3, 5, Relationship::MetInPerson, 12));
+
+        // Pairs that existed before version 6 are counted by the migration
+        <MutualCount<Test>>::remove(1);
+        <MutualCount<Test>>::insert(3, 1);
+        StorageVersion::new(5).put::<SocialGraph>();
+        crate::migrations::v6::migrate::<Test>();
+        assert_eq!(SocialGraph::on_chain_storage_version(), 6);
+        assert_eq!(SocialGraph::mutual_count(1), 1);
+        assert_eq!(SocialGraph::mutual_count(2), 1);
+        assert_eq!(SocialGraph::mutual_count(3), 0);
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn set_parameters_test() {
+    new_test_ext().execute_with(|| {
+        let parameters = GraphParameters { challenge_duration: 20, max_confidence: 5, ..test_parameters() };
+
+        // Only the admin origin can change parameters
+        assert_noop!(SocialGraph::set_parameters(Origin::signed(1), parameters.clone()), BadOrigin);
+
+        // Values must be within sane bounds
+        let zero_duration = GraphParameters { challenge_duration: 0, ..parameters.clone() };
+        assert_noop!(SocialGraph::set_parameters(Origin::root(), zero_duration), Error::<Test>::InvalidParameters);
+        let long_duration = GraphParameters { challenge_duration: 101, ..parameters.clone() };
+        assert_noop!(SocialGraph::set_parameters(Origin::root(), long_duration), Error::<Test>::InvalidParameters);
+        let zero_confidence = GraphParameters { max_confidence: 0, ..parameters.clone() };
+        assert_noop!(SocialGraph::set_parameters(Origin::root(), zero_confidence), Error::<Test>::InvalidParameters);
+
+        assert_ok!(SocialGraph::set_parameters(Origin::root(), parameters.clone()));
+        assert_eq!(SocialGraph::parameters(), parameters);
+
+        // New bounds apply right away
+        register(1..3);
+        assert_noop!(
+            SocialGraph::attest(Origin::signed(1), 2, 6, Relationship::MetInPerson, 12),
+            Error::<Test>::ConfidenceOutOfBounds
+        );
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_eq!(SocialGraph::challenges().to_vec(), vec![(2, 20)]);
+
+        // Mutual thresholds apply to validity
+        let min_mutual = GraphParameters { min_mutual_attestations: 1, ..parameters };
+        assert_ok!(SocialGraph::set_parameters(Origin::root(), min_mutual));
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 2, 5, Relationship::MetInPerson, 12));
+        assert_noop!(
+            SocialGraph::attest(Origin::signed(2), 1, 5, Relationship::MetInPerson, 12),
+            Error::<Test>::InvalidAttester
+        );
+    });
+}
+
+#[test]
+fn challenge_bond_test() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(SocialGraph::set_parameters(
+            Origin::root(),
+            GraphParameters { challenge_bond: 20, ..test_parameters() }
+        ));
+        register(1..4);
+
+        // Challenging reserves the bond
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_ok!(SocialGraph::challenge(Origin::signed(3), 1));
+        assert_eq!(Balances::reserved_balance(1), 10 + 20);
+
+        // A banned suspect returns the bond
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, -5));
+        // An acquitted suspect slashes the bond
+        assert_ok!(SocialGraph::vote(Origin::signed(2), 1, 5));
+
+        // Challenges resolve once their final block is reached
+        SocialGraph::on_initialize(7);
+        assert_eq!(SocialGraph::challenges().len(), 2);
+        SocialGraph::on_initialize(8);
+        assert!(SocialGraph::challenges().is_empty());
+        assert!(SocialGraph::banned_accounts(2).is_some());
+        assert!(SocialGraph::banned_accounts(1).is_none());
+        assert_eq!(Balances::reserved_balance(1), 10);
+        assert_eq!(Balances::free_balance(1), 100 - 10);
+        assert_eq!(Balances::reserved_balance(3), 10);
+        assert_eq!(Balances::free_balance(3), 100 - 10 - 20);
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn moderation_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(SocialGraph::set_parameters(
+            Origin::root(),
+            GraphParameters { challenge_bond: 20, ..test_parameters() }
+        ));
+        register(1..4);
+
+        // Bans and unbans
+        assert_noop!(SocialGraph::force_ban(Origin::signed(1), 3, 7), BadOrigin);
+        assert_ok!(SocialGraph::force_ban(Origin::root(), 3, 7));
+        System::assert_has_event(Event::SocialGraph(crate::Event::ForceBanned(3, 7)));
+        assert_noop!(SocialGraph::force_ban(Origin::root(), 3, 7), Error::<Test>::AlreadyBanned);
+        assert_ok!(SocialGraph::force_unban(Origin::root(), 3, 8));
+        assert_noop!(SocialGraph::force_unban(Origin::root(), 3, 8), Error::<Test>::NotBanned);
+
+        // Force resolving bans the suspect, clears votes and returns the bond
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, 5));
+        assert_ok!(SocialGraph::force_resolve_challenge(Origin::root(), 2, true));
+        assert!(SocialGraph::banned_accounts(2).is_some());
+        assert!(SocialGraph::stale_votes(2).is_some());
+        assert_eq!(SocialGraph::tallies(2), (0, 0));
+        assert_eq!(Balances::reserved_balance(1), 10);
+        assert_ok!(SocialGraph::do_try_state());
+
+        // Force cancelling returns the bond without a verdict
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 3));
+        assert_ok!(SocialGraph::force_cancel_challenge(Origin::root(), 3));
+        assert!(SocialGraph::banned_accounts(3).is_none());
+        assert_eq!(Balances::reserved_balance(1), 10);
+        assert_noop!(SocialGraph::force_cancel_challenge(Origin::root(), 3), Error::<Test>::ChallengeNotFound);
+        assert_ok!(SocialGraph::do_try_state());
+
+        // Force removing an attestation corrects the counters
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 3, 5, Relationship::MetInPerson, 12));
+        assert_ok!(SocialGraph::attest(Origin::signed(3), 1, 5, Relationship::MetInPerson, 12));
+        assert_ok!(SocialGraph::force_remove_attestation(Origin::root(), 3, 1));
+        assert_eq!(<AccountData<Test>>::get(3).map(|data| (data.0, data.1)), Some((0, 0)));
+        assert_eq!(<TotalsCounter<Test>>::get(), Some((1, 10)));
+        assert_eq!(SocialGraph::mutual_count(1), 0);
+        assert_noop!(
+            SocialGraph::force_remove_attestation(Origin::root(), 3, 1),
+            Error::<Test>::AttestationNotFound
+        );
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn council_veto_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(SocialGraph::set_parameters(
+            Origin::root(),
+            GraphParameters { challenge_bond: 20, veto_period: 5, ..test_parameters() }
+        ));
+        register(1..5);
+
+        // A finished challenge waits for the veto period
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_ok!(SocialGraph::challenge(Origin::signed(3), 4));
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, -5));
+        assert_ok!(SocialGraph::vote(Origin::signed(1), 4, -5));
+        SocialGraph::on_initialize(8);
+        System::assert_has_event(Event::SocialGraph(crate::Event::VerdictPending(2, true, 13)));
+        assert!(SocialGraph::banned_accounts(2).is_none());
+        assert_eq!(SocialGraph::verdict_queue().len(), 2);
+        assert_noop!(SocialGraph::challenge(Origin::signed(3), 2), Error::<Test>::VerdictPending);
+        assert_noop!(SocialGraph::leave(Origin::signed(2), 0), Error::<Test>::UnderChallenge);
+        assert_ok!(SocialGraph::do_try_state());
+
+        // The council overturns one verdict, slashing its bond
+        assert_noop!(SocialGraph::overturn_verdict(Origin::signed(1), 4), BadOrigin);
+        assert_ok!(SocialGraph::overturn_verdict(Origin::root(), 4));
+        System::assert_has_event(Event::SocialGraph(crate::Event::VerdictOverturned(4, false)));
+        assert!(SocialGraph::banned_accounts(4).is_none());
+        assert_eq!(Balances::reserved_balance(3), 10);
+        assert_eq!(Balances::free_balance(3), 100 - 10 - 20);
+        assert_noop!(SocialGraph::overturn_verdict(Origin::root(), 4), Error::<Test>::VerdictNotFound);
+
+        // The other is enacted once the veto period ends
+        SocialGraph::on_initialize(12);
+        assert!(SocialGraph::banned_accounts(2).is_none());
+        SocialGraph::on_initialize(13);
+        System::assert_has_event(Event::SocialGraph(crate::Event::VerdictEnacted(2, true)));
+        assert!(SocialGraph::banned_accounts(2).is_some());
+        assert_eq!(Balances::reserved_balance(1), 10);
+        assert!(SocialGraph::verdict_queue().is_empty());
+        assert_ok!(SocialGraph::do_try_state());
+
+        // The council is elected from valid members in good standing
+        SocialGraph::on_initialize(20);
+        assert_eq!(SocialGraph::council_members().into_inner(), vec![1, 3, 4]);
+
+        // Pending verdicts count towards MaxChallenges, so no verdict skips its veto period
+        let queue: Vec<(u64, u64)> = (100..1_099).map(|suspect| (suspect, 50)).collect();
+        <VerdictQueue<Test>>::put(BoundedVec::try_from(queue).unwrap());
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 3));
+        assert_noop!(SocialGraph::challenge(Origin::signed(3), 1), Error::<Test>::MaxChallengesReached);
+    });
+}
+
+#[test]
+fn council_candidates_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..8);
+        let ranked = || -> Vec<u64> {
+            SocialGraph::council_candidates().into_iter().map(|(account, _, _)| account).collect()
+        };
+
+        // Only the best `MaxCouncilCandidates` accounts are kept
+        assert_eq!(ranked(), vec![1, 2, 3, 4, 5]);
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 7, 5, Relationship::MetInPerson, 12));
+        assert_eq!(ranked(), vec![7, 1, 2, 3, 4]);
+
+        // Banned accounts drop out until they are unbanned
+        assert_ok!(SocialGraph::force_ban(Origin::root(), 7, 0));
+        assert_eq!(ranked(), vec![1, 2, 3, 4]);
+        assert_ok!(SocialGraph::force_unban(Origin::root(), 7, 0));
+        assert_eq!(ranked(), vec![7, 1, 2, 3, 4]);
+        assert_ok!(SocialGraph::leave(Origin::signed(7), 1));
+        assert_eq!(ranked(), vec![1, 2, 3, 4]);
+        assert_ok!(SocialGraph::do_try_state());
+
+        // The election refills the candidates that left
+        SocialGraph::on_initialize(20);
+        assert_eq!(ranked(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(SocialGraph::council_members().len(), 3);
+    });
+}
+
+#[test]
+fn attest_many_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..6);
+
+        // The whole batch is rejected if one entry is invalid
+        let batch = |entries: Vec<(u64, u8)>| BoundedVec::try_from(entries).unwrap();
+        assert_noop!(
+            SocialGraph::attest_many(Origin::signed(1), batch(vec![(2, 5), (3, 11)]), Relationship::MetInPerson, 0),
+            Error::<Test>::ConfidenceOutOfBounds
+        );
+        assert_noop!(
+            SocialGraph::attest_many(Origin::signed(1), batch(vec![(2, 5), (1, 5)]), Relationship::MetInPerson, 0),
+            Error::<Test>::SelfAttestationError
+        );
+        assert_noop!(
+            SocialGraph::attest_many(Origin::signed(1), batch(vec![(2, 5), (9, 5)]), Relationship::MetInPerson, 0),
+            Error::<Test>::UnregisteredTarget
+        );
+        assert_noop!(
+            SocialGraph::attest_many(Origin::signed(1), batch(vec![(2, 5), (2, 6)]), Relationship::MetInPerson, 0),
+            Error::<Test>::DuplicateTarget
+        );
+
+        // A valid batch writes every edge and emits one event each
+        assert_ok!(SocialGraph::attest(Origin::signed(2), 1, 4, Relationship::MetInPerson, 12));
+        assert_ok!(SocialGraph::attest_many(
+            Origin::signed(1),
+            batch(vec![(2, 5), (3, 6), (4, 7)]),
+            Relationship::MetInPerson,
+            0
+        ));
+        assert_eq!(<AccountData<Test>>::get(3).map(|data| data.1), Some(12));
+        assert_eq!(<TotalsCounter<Test>>::get(), Some((4, 8 + 10 + 12 + 14)));
+        assert_eq!(SocialGraph::mutual_count(1), 1);
+        System::assert_has_event(Event::SocialGraph(crate::Event::Attested(
+            1, 4, Attestation { confidence: 7, relationship: Relationship::MetInPerson, known_for: 0, block: 1 }
+        )));
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn attest_signed_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..4);
+        let payload = AttestationPayload {
+            attester: 1,
+            target: 2,
+            confidence: 7,
+            relationship: Relationship::MetInPerson,
+            known_for: 12,
+            nonce: 0,
+            expiry: 5,
+        };
+        let sign = |signer: u64, payload: &AttestationPayload<u64, u64>| {
+            TestSignature(signer, SocialGraph::attestation_message(payload))
+        };
+
+        // The signature must come from the attester and cover the context and genesis hash
+        assert_noop!(
+            SocialGraph::attest_signed(Origin::signed(9), payload.clone(), sign(3, &payload)),
+            Error::<Test>::InvalidSignature
+        );
+        assert_noop!(
+            SocialGraph::attest_signed(Origin::signed(9), payload.clone(), TestSignature(1, payload.encode())),
+            Error::<Test>::InvalidSignature
+        );
+
+        // Any account can relay a correctly signed payload
+        assert_ok!(SocialGraph::attest_signed(Origin::signed(9), payload.clone(), sign(1, &payload)));
+        assert_eq!(<Attestations<Test>>::get(2, 1).map(|a| a.confidence), Some(7));
+        assert_eq!(SocialGraph::attest_nonces(1), 1);
+        assert_eq!(Balances::free_balance(1), 100 - 10);
+
+        // A payload can't be replayed
+        assert_noop!(
+            SocialGraph::attest_signed(Origin::signed(9), payload.clone(), sign(1, &payload)),
+            Error::<Test>::InvalidNonce
+        );
+
+        // Nor submitted after its expiry
+        let late = AttestationPayload { nonce: 1, ..payload };
+        System::set_block_number(6);
+        assert_noop!(
+            SocialGraph::attest_signed(Origin::signed(9), late.clone(), sign(1, &late)),
+            Error::<Test>::PayloadExpired
+        );
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn handshake_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..4);
+        let claim = HandshakeClaim {
+            first: 1,
+            second: 2,
+            first_confidence: 6,
+            second_confidence: 8,
+            session: 42,
+            expiry: 5,
+        };
+        let sign = |signer: u64, claim: &HandshakeClaim<u64, u64>| {
+            TestSignature(signer, SocialGraph::handshake_message(claim))
+        };
+
+        // Both parties must sign the tagged claim and one of them must submit
+        assert_noop!(
+            SocialGraph::handshake(Origin::signed(1), claim.clone(), sign(1, &claim), sign(3, &claim)),
+            Error::<Test>::InvalidSignature
+        );
+        assert_noop!(
+            SocialGraph::handshake(
+                Origin::signed(1),
+                claim.clone(),
+                TestSignature(1, claim.encode()),
+                TestSignature(2, claim.encode())
+            ),
+            Error::<Test>::InvalidSignature
+        );
+        assert_noop!(
+            SocialGraph::handshake(Origin::signed(3), claim.clone(), sign(1, &claim), sign(2, &claim)),
+            Error::<Test>::NotHandshakeParty
+        );
+
+        // The handshake writes both edges, marked as in-person
+        assert_ok!(SocialGraph::handshake(Origin::signed(2), claim.clone(), sign(1, &claim), sign(2, &claim)));
+        let edge = <Attestations<Test>>::get(2, 1).unwrap();
+        assert_eq!(edge.confidence, 6);
+        assert_eq!(edge.relationship, Relationship::InPersonHandshake);
+        assert_eq!(<Attestations<Test>>::get(1, 2).map(|a| a.confidence), Some(8));
+        assert_eq!(<TotalsCounter<Test>>::get(), Some((2, 6 * 3 + 8 * 3)));
+        assert_eq!(SocialGraph::mutual_count(1), 1);
+        System::assert_has_event(Event::SocialGraph(crate::Event::HandshakeCompleted(1, 2, 42)));
+
+        // Sessions can't be reused and claims expire
+        assert_noop!(
+            SocialGraph::handshake(Origin::signed(1), claim.clone(), sign(1, &claim), sign(2, &claim)),
+            Error::<Test>::SessionAlreadyUsed
+        );
+        let late = HandshakeClaim { second: 3, session: 43, ..claim };
+        System::set_block_number(6);
+        assert_noop!(
+            SocialGraph::handshake(Origin::signed(1), late.clone(), sign(1, &late), sign(3, &late)),
+            Error::<Test>::PayloadExpired
+        );
+
+        // Only a handshake can record the in-person marker
+        assert_noop!(
+            SocialGraph::attest(Origin::signed(3), 1, 5, Relationship::InPersonHandshake, 0),
+            Error::<Test>::ReservedRelationship
+        );
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn notary_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..6);
+        let hash = H256::repeat_byte(7);
+
+        // Only valid persons can notarize, once per document
+        assert_noop!(SocialGraph::notarize(Origin::signed(9), hash, None), Error::<Test>::InvalidSigner);
+        let metadata = BoundedVec::try_from(b"lease".to_vec()).unwrap();
+        assert_ok!(SocialGraph::notarize(Origin::signed(1), hash, Some(metadata)));
+        assert_noop!(SocialGraph::notarize(Origin::signed(2), hash, None), Error::<Test>::AlreadyNotarized);
+
+        // Others co-sign, each once and up to the limit
+        assert_noop!(SocialGraph::co_sign(Origin::signed(2), H256::zero()), Error::<Test>::DocumentNotFound);
+        assert_ok!(SocialGraph::co_sign(Origin::signed(2), hash));
+        assert_noop!(SocialGraph::co_sign(Origin::signed(2), hash), Error::<Test>::AlreadySigned);
+
+        // An account that is registered but not valid can't sign
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 2, 5, Relationship::MetInPerson, 12));
+        assert_noop!(SocialGraph::co_sign(Origin::signed(3), hash), Error::<Test>::InvalidSigner);
+        assert_noop!(SocialGraph::notarize(Origin::signed(3), H256::zero(), None), Error::<Test>::InvalidSigner);
+        assert_ok!(SocialGraph::attest(Origin::signed(2), 3, 5, Relationship::MetInPerson, 12));
+        assert_ok!(SocialGraph::co_sign(Origin::signed(3), hash));
+        assert_ok!(SocialGraph::attest(Origin::signed(2), 4, 5, Relationship::MetInPerson, 12));
+        assert_noop!(SocialGraph::co_sign(Origin::signed(4), hash), Error::<Test>::TooManySigners);
+
+        // The record lists every signer with their standing when signing
+        let record = SocialGraph::documents(hash).unwrap();
+        let signers: Vec<(u64, u32)> = record.signers.iter().map(|s| (s.account, s.attest_count)).collect();
+        assert_eq!(signers, vec![(1, 0), (2, 0), (3, 1)]);
+        assert!(record.signers.iter().all(|s| s.block == 1));
+        assert_eq!(record.signer_banned, None);
+
+        // Banning a signer marks the record
+        System::set_block_number(4);
+        assert_ok!(SocialGraph::force_ban(Origin::root(), 2, 0));
+        System::assert_has_event(Event::SocialGraph(crate::Event::DocumentSignerBanned(hash, 2)));
+        assert_eq!(SocialGraph::documents(hash).unwrap().signer_banned, Some(4));
+
+        // Unbanning clears the mark once no banned signer is left
+        System::set_block_number(6);
+        assert_ok!(SocialGraph::force_ban(Origin::root(), 3, 0));
+        assert_ok!(SocialGraph::force_unban(Origin::root(), 2, 0));
+        System::assert_has_event(Event::SocialGraph(crate::Event::DocumentSignerUnbanned(hash, 2)));
+        assert_eq!(SocialGraph::documents(hash).unwrap().signer_banned, Some(6));
+        assert_ok!(SocialGraph::force_unban(Origin::root(), 3, 0));
+        assert_eq!(SocialGraph::documents(hash).unwrap().signer_banned, None);
+
+        // Co-signers withdraw their own signature, the submitter the whole document
+        assert_noop!(SocialGraph::unsign(Origin::signed(4), hash), Error::<Test>::NotSigned);
+        assert_ok!(SocialGraph::unsign(Origin::signed(3), hash));
+        System::assert_has_event(Event::SocialGraph(crate::Event::DocumentUnsigned(hash, 3)));
+        assert!(SocialGraph::signed_documents(3).is_empty());
+        assert_eq!(SocialGraph::documents(hash).unwrap().signers.len(), 2);
+        assert_ok!(SocialGraph::unsign(Origin::signed(1), hash));
+        System::assert_has_event(Event::SocialGraph(crate::Event::DocumentRemoved(hash, 1)));
+        assert!(SocialGraph::documents(hash).is_none());
+        assert!(SocialGraph::signed_documents(1).is_empty() && SocialGraph::signed_documents(2).is_empty());
+    });
+}
+
+#[test]
+fn recovery_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..5);
+        // 2 and 3 vouch for each other, then attest for 1
+        let claim = HandshakeClaim {
+            first: 2,
+            second: 3,
+            first_confidence: 6,
+            second_confidence: 6,
+            session: 0,
+            expiry: 5,
+        };
+        let encoded = SocialGraph::handshake_message(&claim);
+        assert_ok!(SocialGraph::handshake(
+            Origin::signed(2),
+            claim,
+            TestSignature(2, encoded.clone()),
+            TestSignature(3, encoded)
+        ));
+        assert_ok!(SocialGraph::attest(Origin::signed(2), 1, 6, Relationship::MetInPerson, 12));
+        assert_ok!(SocialGraph::attest(Origin::signed(3), 1, 2, Relationship::MetInPerson, 12));
+
+        // A new account starts the recovery and the old key can cancel it
+        assert_noop!(SocialGraph::initiate_recovery(Origin::signed(2), 1), Error::<Test>::AlreadyRegistered);
+        assert_ok!(SocialGraph::initiate_recovery(Origin::signed(10), 1));
+        assert_noop!(SocialGraph::initiate_recovery(Origin::signed(11), 1), Error::<Test>::RecoveryInProgress);
+        assert_ok!(SocialGraph::cancel_recovery(Origin::signed(1)));
+
+        // Attesters can reject a recovery they don't recognize
+        assert_ok!(SocialGraph::initiate_recovery(Origin::signed(11), 1));
+        assert_noop!(SocialGraph::reject_recovery(Origin::signed(4), 1), Error::<Test>::NotAGuardian);
+        assert_ok!(SocialGraph::reject_recovery(Origin::signed(2), 1));
+        System::assert_has_event(Event::SocialGraph(crate::Event::RecoveryCancelled(1)));
+        assert!(SocialGraph::recoveries(1).is_none());
+
+        // A recovery that doesn't reach its threshold in time expires and is replaced
+        assert_ok!(SocialGraph::initiate_recovery(Origin::signed(11), 1));
+        assert_ok!(SocialGraph::confirm_recovery(Origin::signed(3), 1));
+        System::set_block_number(31);
+        assert_noop!(SocialGraph::confirm_recovery(Origin::signed(2), 1), Error::<Test>::RecoveryExpired);
+        assert_ok!(SocialGraph::initiate_recovery(Origin::signed(10), 1));
+        assert_eq!(SocialGraph::recoveries(1).unwrap().rescuer, 10);
+        assert_eq!(SocialGraph::recovery_confirmations(1, 3), None);
+
+        // Attesters confirm, weighted by confidence
+        assert_noop!(SocialGraph::confirm_recovery(Origin::signed(4), 1), Error::<Test>::NotAGuardian);
+        assert_ok!(SocialGraph::confirm_recovery(Origin::signed(3), 1));
+        assert_noop!(SocialGraph::confirm_recovery(Origin::signed(3), 1), Error::<Test>::AlreadyConfirmed);
+        assert_noop!(SocialGraph::claim_recovery(Origin::signed(10), 1, 2), Error::<Test>::ThresholdNotReached);
+        assert_ok!(SocialGraph::confirm_recovery(Origin::signed(2), 1));
+        assert_eq!(SocialGraph::recoveries(1).unwrap().threshold_reached, Some(31));
+
+        // The claim waits for the delay
+        assert_noop!(SocialGraph::claim_recovery(Origin::signed(11), 1, 2), Error::<Test>::NotRescuer);
+        assert_noop!(SocialGraph::claim_recovery(Origin::signed(10), 1, 2), Error::<Test>::RecoveryDelayPending);
+        System::set_block_number(41);
+        assert_noop!(SocialGraph::claim_recovery(Origin::signed(10), 1, 1), Error::<Test>::WitnessTooLow);
+        assert_ok!(SocialGraph::claim_recovery(Origin::signed(10), 1, 2));
+        System::assert_has_event(Event::SocialGraph(crate::Event::AccountRecovered(1, 10)));
+        System::assert_has_event(Event::SocialGraph(crate::Event::AttestationRemoved(1, 2)));
+        assert!(System::events().iter().any(|record| {
+            matches!(record.event, Event::SocialGraph(crate::Event::Attested(2, 10, _)))
+        }));
+
+        // The new account inherits the old one's place, the old one is retired
+        assert_eq!(<AccountData<Test>>::get(10), Some((2, 16, 1)));
+        assert_eq!(<AccountData<Test>>::get(1), None);
+        assert!(<Attestations<Test>>::get(10, 2).is_some());
+        assert_eq!(<Attestations<Test>>::iter_prefix(1).count(), 0);
+        assert_eq!(Balances::reserved_balance(10), 10);
+        assert_eq!(Balances::reserved_balance(1), 0);
+        assert_noop!(SocialGraph::register(Origin::signed(1)), Error::<Test>::AccountRetired);
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn rotate_account_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..5);
+        // 1 and 2 vouch for each other, 1 attests for 3 and votes on 4
+        let claim = HandshakeClaim {
+            first: 1,
+            second: 2,
+            first_confidence: 6,
+            second_confidence: 6,
+            session: 0,
+            expiry: 5,
+        };
+        let encoded = SocialGraph::handshake_message(&claim);
+        assert_ok!(SocialGraph::handshake(
+            Origin::signed(1),
+            claim,
+            TestSignature(1, encoded.clone()),
+            TestSignature(2, encoded)
+        ));
+        assert_ok!(SocialGraph::attest(Origin::signed(1), 3, 6, Relationship::MetInPerson, 12));
+        assert_ok!(SocialGraph::challenge(Origin::signed(2), 4));
+        assert_ok!(SocialGraph::vote(Origin::signed(1), 4, 3));
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 3));
+
+        // The new key must countersign
+        let rotation = (ROTATION_CONTEXT, 1u64, 10u64).encode();
+        assert_noop!(
+            SocialGraph::rotate_account(Origin::signed(1), 10, TestSignature(1, rotation.clone())),
+            Error::<Test>::InvalidSignature
+        );
+        // and must not hold a vote from an earlier registration
+        <Votes<Test>>::insert(4, 10, 1);
+        assert_noop!(
+            SocialGraph::rotate_account(Origin::signed(1), 10, TestSignature(10, rotation.clone())),
+            Error::<Test>::NewAccountVoted
+        );
+        <Votes<Test>>::remove(4, 10);
+        assert_ok!(SocialGraph::rotate_account(Origin::signed(1), 10, TestSignature(10, rotation)));
+        assert_eq!(<AccountData<Test>>::get(10), Some((1, 18, 1)));
+        assert_eq!(SocialGraph::votes(4, 10), Some(3));
+        assert_eq!(Balances::reserved_balance(10), 10);
+        assert_noop!(
+            SocialGraph::attest(Origin::signed(2), 10, 5, Relationship::MetInPerson, 12),
+            Error::<Test>::RotationInProgress
+        );
+
+        // Attestations are re-keyed over several blocks
+        SocialGraph::on_initialize(2);
+        assert_eq!(SocialGraph::rotation_queue().len(), 1);
+        for n in 3..7 {
+            SocialGraph::on_initialize(n);
+        }
+        System::assert_has_event(Event::SocialGraph(crate::Event::AccountRotated(1, 10)));
+        System::assert_has_event(Event::SocialGraph(crate::Event::AttestationRemoved(1, 2)));
+        System::assert_has_event(Event::SocialGraph(crate::Event::AttestationRemoved(3, 1)));
+        let attested = |attester: u64, target: u64| System::events().iter().any(|record| {
+            matches!(record.event, Event::SocialGraph(crate::Event::Attested(a, t, _)) if (a, t) == (attester, target))
+        });
+        assert!(attested(2, 10) && attested(10, 3));
+        assert!(SocialGraph::rotation_queue().is_empty());
+        assert!(<Attestations<Test>>::get(10, 2).is_some());
+        assert!(<Attestations<Test>>::get(2, 10).is_some());
+        assert!(<Attestations<Test>>::get(3, 10).is_some());
+        assert_eq!(<Attestations<Test>>::iter_prefix(1).count(), 0);
+        assert_eq!(<ChallengesByChallenger<Test>>::iter_prefix(10).count(), 1);
+        assert_eq!(<ChallengesByChallenger<Test>>::iter_prefix(1).count(), 0);
+        assert_eq!(SocialGraph::mutual_count(10), 1);
+        assert_eq!(<TotalsCounter<Test>>::get(), Some((3, 18 + 18 + 12)));
+        assert_noop!(SocialGraph::register(Origin::signed(1)), Error::<Test>::AccountRetired);
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+fn withdraw_cancel_extend_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_ok!(SocialGraph::set_parameters(
+            Origin::root(),
+            GraphParameters { challenge_bond: 20, ..test_parameters() }
+        ));
+        register(1..5);
+
+        // Votes can be withdrawn while the challenge is active
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, 5));
+        assert_ok!(SocialGraph::withdraw_vote(Origin::signed(3), 2));
+        assert_eq!(SocialGraph::votes(2, 3), None);
+        assert_eq!(SocialGraph::challenge_records(0).unwrap().status, ChallengeStatus::Evidence);
+        assert_noop!(SocialGraph::withdraw_vote(Origin::signed(3), 2), Error::<Test>::VoteNotFound);
+
+        // The challenger can extend once while turnout is low
+        assert_noop!(SocialGraph::extend_challenge(Origin::signed(3), 2), Error::<Test>::InvalidChallenger);
+        assert_ok!(SocialGraph::extend_challenge(Origin::signed(1), 2));
+        assert_eq!(SocialGraph::challenges().into_inner(), vec![(2, 17)]);
+        assert_noop!(SocialGraph::extend_challenge(Origin::signed(1), 2), Error::<Test>::AlreadyExtended);
+        assert_ok!(SocialGraph::do_try_state());
+
+        // A third-party vote prevents cancelling
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, 5));
+        assert_noop!(SocialGraph::cancel_challenge(Origin::signed(1), 2), Error::<Test>::ChallengeHasVotes);
+        assert_ok!(SocialGraph::withdraw_vote(Origin::signed(3), 2));
+
+        // Cancelling refunds half the bond and slashes the rest
+        assert_ok!(SocialGraph::vote(Origin::signed(1), 2, -5));
+        assert_ok!(SocialGraph::cancel_challenge(Origin::signed(1), 2));
+        System::assert_has_event(Event::SocialGraph(crate::Event::ChallengeCancelled(2, 10)));
+        assert!(SocialGraph::challenges().is_empty());
+        assert!(SocialGraph::stale_votes(2).is_some());
+        assert_eq!(Balances::reserved_balance(1), 10);
+        assert_eq!(Balances::free_balance(1), 100 - 10 - 10);
+        assert_ok!(SocialGraph::do_try_state());
+
+        // Turnout at the threshold blocks the extension
+        assert_noop!(SocialGraph::challenge(Origin::signed(1), 2), Error::<Test>::StaleVotesPending);
+        assert_ok!(SocialGraph::clear_stale_votes(Origin::signed(9), 2));
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, 5));
+        assert_ok!(SocialGraph::vote(Origin::signed(4), 2, 5));
+        assert_noop!(SocialGraph::extend_challenge(Origin::signed(1), 2), Error::<Test>::TurnoutReached);
+    });
+}
+
+#[test]
+fn challenge_history_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..5);
+
+        // Each challenge gets an ID and a record
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 3));
+        System::assert_has_event(Event::SocialGraph(crate::Event::ChallengeCreated(1, 1, 3, 9)));
+        assert_eq!(SocialGraph::open_challenges(2), Some(0));
+        assert_eq!(SocialGraph::challenge_records(0).unwrap().status, ChallengeStatus::Evidence);
+
+        // Voting moves it on, cancelling closes it
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, -4));
+        assert_ok!(SocialGraph::vote(Origin::signed(4), 2, -2));
+        assert_eq!(SocialGraph::challenge_records(0).unwrap().status, ChallengeStatus::Voting);
+        assert_ok!(SocialGraph::cancel_challenge(Origin::signed(1), 3));
+        assert_eq!(SocialGraph::challenge_records(1).unwrap().status, ChallengeStatus::Cancelled);
+        assert_eq!(SocialGraph::open_challenges(3), None);
+
+        // Resolution keeps the outcome
+        SocialGraph::on_initialize(9);
+        let outcome = ChallengeOutcome { tally: -6, turnout: 2, banned: true, resolved: 9, enacted: Some(9) };
+        System::assert_has_event(Event::SocialGraph(crate::Event::ChallengeResolved(
+            0, 2, ChallengeOutcome { enacted: None, ..outcome.clone() }
+        )));
+        let record = SocialGraph::challenge_records(0).unwrap();
+        assert_eq!(record.status, ChallengeStatus::Resolved);
+        assert_eq!(record.outcome, Some(outcome));
+        assert_eq!(SocialGraph::open_challenges(2), None);
+
+        // A new challenge of the same suspect gets its own record
+        assert_ok!(SocialGraph::force_unban(Origin::root(), 2, 0));
+        assert_ok!(SocialGraph::clear_stale_votes(Origin::signed(9), 2));
+        assert_ok!(SocialGraph::challenge(Origin::signed(3), 2));
+        let suspect_history: Vec<ChallengeId> = <ChallengesBySuspect<Test>>::iter_key_prefix(2).collect();
+        assert_eq!(suspect_history.len(), 2);
+        assert_eq!(<ChallengesByChallenger<Test>>::iter_key_prefix(1).count(), 2);
+        assert_ok!(SocialGraph::do_try_state());
+
+        // IDs are never reused
+        <NextChallengeId<Test>>::put(ChallengeId::MAX);
+        assert_noop!(SocialGraph::challenge(Origin::signed(1), 4), Error::<Test>::ChallengeIdOverflow);
+    });
+}
+
+#[test]
+fn running_tally_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..6);
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+
+        // Votes update the tally as they come in, including overwrites
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, -4));
+        assert_ok!(SocialGraph::vote(Origin::signed(4), 2, 3));
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, -6));
+        assert_eq!(SocialGraph::tallies(2), (-3, 2));
+        assert_ok!(SocialGraph::vote(Origin::signed(5), 2, -1));
+        assert_noop!(SocialGraph::vote(Origin::signed(1), 2, -1), Error::<Test>::TooManyVoters);
+        assert_ok!(SocialGraph::withdraw_vote(Origin::signed(5), 2));
+        assert_eq!(SocialGraph::tallies(2), (-3, 2));
+        assert_ok!(SocialGraph::do_try_state());
+
+        // Resolution uses the tally and leaves the votes for cleanup
+        SocialGraph::on_initialize(9);
+        assert!(SocialGraph::banned_accounts(2).is_some());
+        assert!(SocialGraph::stale_votes(2).is_some());
+        assert_eq!(SocialGraph::votes(2, 3), Some(-6));
+        assert_ok!(SocialGraph::do_try_state());
+
+        // Spare weight deletes them, the mock counts one unit per vote
+        SocialGraph::on_idle(9, 1);
+        assert_eq!(<Votes<Test>>::iter_prefix(2).count(), 1);
+        SocialGraph::on_idle(9, 10);
+        assert_eq!(<Votes<Test>>::iter_prefix(2).count(), 0);
+        assert!(SocialGraph::stale_votes(2).is_none());
+
+        // Or anyone can clear them
+        assert_ok!(SocialGraph::force_unban(Origin::root(), 2, 0));
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, 1));
+        assert_ok!(SocialGraph::force_cancel_challenge(Origin::root(), 2));
+        assert_ok!(SocialGraph::clear_stale_votes(Origin::signed(9), 2));
+        assert_eq!(SocialGraph::votes(2, 3), None);
+        assert_noop!(SocialGraph::clear_stale_votes(Origin::signed(9), 2), Error::<Test>::NoStaleVotes);
+    });
+}
+
+#[test]
+fn graph_stats_test() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        register(1..5);
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 2));
+        assert_ok!(SocialGraph::challenge(Origin::signed(1), 3));
+        let stats = SocialGraph::graph_stats(10);
+        assert_eq!(stats.accounts, 4);
+        assert_eq!(stats.active_challenges, 2);
+        // The counted totals match without the walked fields
+        let totals = SocialGraph::graph_totals();
+        assert_eq!((totals.accounts, totals.active_challenges, totals.valid_accounts), (4, 2, 0));
+
+        // One challenge bans its suspect, the other is cancelled
+        assert_ok!(SocialGraph::vote(Origin::signed(3), 2, -4));
+        assert_ok!(SocialGraph::cancel_challenge(Origin::signed(1), 3));
+        SocialGraph::on_initialize(9);
+        let stats = SocialGraph::graph_stats(10);
+        assert_eq!(stats.active_challenges, 0);
+        assert_eq!(stats.banned_accounts, 1);
+        assert_eq!(stats.challenges_banned, 1);
+        assert_eq!(stats.challenges_cancelled, 1);
+        assert_eq!(stats.challenges_cleared, 0);
+
+        // The ban only counts as recent within the window
+        System::set_block_number(20);
+        assert_eq!(SocialGraph::graph_stats(11).recent_bans, 1);
+        assert_eq!(SocialGraph::graph_stats(5).recent_bans, 0);
+    });
+}
+
+#[test]
+fn genesis_graph_test() {
+    let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+    crate::GenesisConfig::<Test> {
+        parameters: test_parameters(),
+        attestations: vec![(1, 2, 8), (2, 1, 6), (3, 1, 10)],
+        banned: vec![4],
+        challenges: vec![(1, 2)],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    sp_io::TestExternalities::from(t).execute_with(|| {
+        // Both ends of each attestation are registered and counted
+        assert_eq!(SocialGraph::account_data(1).map(|(count, _, _)| count), Some(2));
+        assert_eq!(SocialGraph::account_data(3).map(|(count, _, _)| count), Some(0));
+        assert_eq!(SocialGraph::attest_count().map(|(count, _)| count), Some(3));
+        assert_eq!(SocialGraph::mutual_count(1), 1);
+        assert!(SocialGraph::banned_accounts(4).is_some());
+
+        // The challenge is open from the start, without a bond
+        assert_eq!(SocialGraph::open_challenges(2), Some(0));
+        assert_eq!(SocialGraph::challenges().into_inner(), vec![(2, 8)]);
+        assert_eq!(SocialGraph::challenge_bonds(2), None);
+        assert_ok!(SocialGraph::do_try_state());
+    });
+}
+
+#[test]
+#[should_panic(expected = "Genesis attestation is a self-attestation")]
+fn genesis_self_attestation_test() {
+    crate::GenesisConfig::<Test> {
+        parameters: test_parameters(),
+        attestations: vec![(1, 1, 5)],
+        banned: vec![],
+        challenges: vec![],
+    }
+    .build_storage()
+    .unwrap();
+}
@@ -14,7 +14,9 @@ use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
-	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
+	traits::{
+		AccountIdLookup, BlakeTwo256, Block as BlockT, Convert, IdentifyAccount, NumberFor, Verify,
+	},
 	transaction_validity::{TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
@@ -37,12 +39,14 @@ pub use frame_support::{
 	StorageValue,
 };
 pub use frame_system::Call as SystemCall;
+use frame_system::EnsureRoot;
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
+pub use social_graph::Call as SocialGraphCall;
 use pallet_transaction_payment::CurrencyAdapter;
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
-pub use sp_runtime::{Perbill, Permill};
+pub use sp_runtime::{Perbill, Percent, Permill};
 
 /// Import the social graph pallet.
 pub use social_graph;
@@ -101,11 +105,12 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   runtime in substitute for the on-chain Wasm runtime unless all of `spec_name`,
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
-	//   the compatible custom types.
-	spec_version: 100,
+	//   the compatible custom types. Bump it with every change that ships a storage migration.
+	spec_version: 106,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	// Bump whenever a call index or a call's arguments change.
+	transaction_version: 5,
 	state_version: 1,
 };
 
@@ -264,11 +269,67 @@ impl pallet_sudo::Config for Runtime {
 }
 
 
+/// Weighs an attestation's confidence by how the attester knows the target.
+/// People met in person count three times as much as online-only contacts.
+pub struct RelationshipWeight;
+
+impl Convert<social_graph::Relationship, u32> for RelationshipWeight {
+	fn convert(relationship: social_graph::Relationship) -> u32 {
+		use social_graph::Relationship::*;
+		match relationship {
+			InPersonHandshake => 4,
+			Family | MetInPerson => 3,
+			Colleague | OrganisationMember | Unspecified => 2,
+			OnlineOnly => 1,
+		}
+	}
+}
+
+parameter_types! {
+	pub const CouncilMotionDuration: BlockNumber = 3 * DAYS;
+}
+
+/// The moderation council, elected by the social graph from its members in
+/// good standing.
+type CouncilCollective = pallet_collective::Instance1;
+impl pallet_collective::Config<CouncilCollective> for Runtime {
+	type Origin = Origin;
+	type Proposal = Call;
+	type Event = Event;
+	type MotionDuration = CouncilMotionDuration;
+	type MaxProposals = ConstU32<100>;
+	type MaxMembers = ConstU32<13>;
+	type DefaultVote = pallet_collective::PrimeDefaultVote;
+	type WeightInfo = pallet_collective::weights::SubstrateWeight<Runtime>;
+}
+
 /// Configure the social_graph in pallets/social-graph.
 impl social_graph::Config for Runtime {
 	type Event = Event;
-	type ChallengeDuration = ConstU32<8>;
+	type MaxChallengeDuration = ConstU32<{ 30 * DAYS }>;
 	type MaxChallenges = ConstU32<1_000>;
+	type MaxVotersPerChallenge = ConstU32<1_000>;
+	type MaxAttestBatch = ConstU32<64>;
+	type Currency = Balances;
+	type OffchainSignature = Signature;
+	type OffchainPublic = <Signature as Verify>::Signer;
+	type AdminOrigin = EnsureRoot<AccountId>;
+	type MaxProfileFieldLength = ConstU32<128>;
+	type ProfileDepositBase = ConstU128<100_000>;
+	type ProfileByteDeposit = ConstU128<1_000>;
+	type RelationshipWeight = RelationshipWeight;
+	type MaxPendingRotations = ConstU32<16>;
+	type RotationStepsPerBlock = ConstU32<64>;
+	type MaxDocumentMetadataLength = ConstU32<256>;
+	type MaxDocumentSigners = ConstU32<64>;
+	type MaxSignedDocuments = ConstU32<1_024>;
+	type RecoveryPeriod = ConstU32<{ 7 * DAYS }>;
+	type Council = Council;
+	type CouncilOrigin =
+		pallet_collective::EnsureProportionAtLeast<AccountId, CouncilCollective, 1, 2>;
+	type CouncilSize = ConstU32<13>;
+	type CouncilTerm = ConstU32<{ 7 * DAYS }>;
+	type MaxCouncilCandidates = ConstU32<64>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -288,6 +349,7 @@ construct_runtime!(
 		Sudo: pallet_sudo,
 		// Include the custom logic from the social-graph in the runtime.
 		SocialGraph: social_graph,
+		Council: pallet_collective::<Instance1>,
 	}
 );
 
@@ -452,6 +514,20 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl social_graph_runtime_api::SocialGraphApi<Block, AccountId, BlockNumber> for Runtime {
+		fn graph_stats(window: BlockNumber) -> social_graph_runtime_api::GraphStats {
+			SocialGraph::graph_stats(window)
+		}
+
+		fn graph_totals() -> social_graph_runtime_api::GraphStats {
+			SocialGraph::graph_totals()
+		}
+
+		fn is_valid(account: AccountId) -> bool {
+			SocialGraph::is_valid(account)
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<Block, Balance> for Runtime {
 		fn query_info(
 			uxt: <Block as BlockT>::Extrinsic,
@@ -529,6 +605,8 @@ impl_runtime_apis! {
 		}
 
 		fn execute_block_no_check(block: Block) -> Weight {
+			// NOTE: this FRAME version has no `try_state` hook, so the social graph's invariant
+			// checks only run from its `post_upgrade` hook, during `on-runtime-upgrade`.
 			Executive::execute_block_no_check(block)
 		}
 	}